@@ -0,0 +1,329 @@
+//! Finding outcome sets priced away from the 1 USDC they pay out.
+
+use crate::fees::effective_price;
+use crate::{
+    ClientResult, ClobClient, Market, OrderSummary, Side, SortedBook, Token, END_CURSOR,
+    INITIAL_CURSOR,
+};
+use rust_decimal::Decimal;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Which way a set of outcomes is mispriced. Exactly one outcome of a set pays 1 USDC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArbKind {
+    /// Buying every outcome at the best ask costs less than 1, fees included.
+    BuyAll,
+    /// Selling every outcome at the best bid raises more than 1, fees included.
+    SellAll,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArbLeg {
+    pub token_id: String,
+    pub outcome: String,
+    pub price: Decimal,
+    /// Size available at `price`.
+    pub size: Decimal,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArbOpportunity {
+    /// Condition ID of a binary market, or the neg-risk market ID of an event.
+    pub market: String,
+    pub kind: ArbKind,
+    pub legs: Vec<ArbLeg>,
+    /// Sum of the leg prices, before fees.
+    pub total_price: Decimal,
+    /// Sets executable at the leg prices: the smallest leg size.
+    pub size: Decimal,
+    /// Profit per set after fees.
+    pub edge: Decimal,
+}
+
+#[derive(Debug, Clone)]
+pub struct ArbScannerConfig {
+    /// Opportunities are reported only when their edge per set is strictly greater than
+    /// this.
+    pub min_edge: Decimal,
+    /// Fee rate applied to every leg. `None` looks up each token with `get_fee_rate_bps`.
+    pub fee_rate_bps: Option<u32>,
+    /// Tokens per `get_order_books` request.
+    pub books_per_request: usize,
+}
+
+impl Default for ArbScannerConfig {
+    fn default() -> Self {
+        Self {
+            min_edge: Decimal::ZERO,
+            fee_rate_bps: None,
+            books_per_request: 50,
+        }
+    }
+}
+
+/// Checks the two outcomes of every binary market, and the YES outcomes of every neg-risk
+/// event, against the top of their books.
+#[derive(Debug, Clone, Default)]
+pub struct ArbScanner {
+    config: ArbScannerConfig,
+}
+
+impl ArbScanner {
+    pub fn new(config: ArbScannerConfig) -> Self {
+        Self { config }
+    }
+
+    /// Fetches the books of `markets`, as returned by `get_markets` or
+    /// `get_sampling_markets`, and returns every opportunity found. Inactive and closed
+    /// markets are skipped. Neg-risk markets are also grouped by `neg_risk_market_id`, and
+    /// the event is checked over the YES outcomes of its listed markets.
+    ///
+    /// Buying a subset of an event's outcomes does not pay out for sure, so `BuyAll` is only
+    /// reported for an event when `markets` holds every market `get_markets` lists for it.
+    /// Listing them takes a pass over all pages of `get_markets`, so the listings are
+    /// cached for [`MetadataCacheConfig::neg_risk_event_ttl`](crate::MetadataCacheConfig).
+    pub async fn scan(
+        &self,
+        client: &ClobClient,
+        markets: &[Market],
+    ) -> ClientResult<Vec<ArbOpportunity>> {
+        let markets: Vec<_> = markets.iter().filter(|m| m.active && !m.closed).collect();
+        let token_ids: Vec<String> = markets
+            .iter()
+            .flat_map(|m| m.tokens.iter().map(|t| t.token_id.clone()))
+            .collect();
+
+        let mut books = HashMap::new();
+        for chunk in token_ids.chunks(self.config.books_per_request.max(1)) {
+            for book in client.get_order_books(chunk).await? {
+                books.insert(book.asset_id.clone(), book.sorted());
+            }
+        }
+        let mut fee_rates = HashMap::new();
+        for token_id in &token_ids {
+            let fee_rate_bps = match self.config.fee_rate_bps {
+                Some(fee_rate_bps) => fee_rate_bps,
+                None => client.get_fee_rate_bps(token_id).await?,
+            };
+            fee_rates.insert(token_id.as_str(), fee_rate_bps);
+        }
+        let outcome = |token: &'_ Token| {
+            let book = books.get(&token.token_id)?;
+            Some((
+                token.clone(),
+                book.clone(),
+                fee_rates[token.token_id.as_str()],
+            ))
+        };
+
+        let mut found = Vec::new();
+        let mut events: BTreeMap<&str, Vec<_>> = BTreeMap::new();
+        let mut members: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+        for market in markets {
+            if let Some(outcomes) = market
+                .tokens
+                .iter()
+                .map(outcome)
+                .collect::<Option<Vec<_>>>()
+            {
+                found.extend(self.check(&market.condition_id, &outcomes));
+            }
+            if let (true, Some(event)) = (market.neg_risk, &market.neg_risk_market_id) {
+                let yes = market
+                    .tokens
+                    .iter()
+                    .find(|t| t.outcome.eq_ignore_ascii_case("yes"))
+                    .unwrap_or(&market.tokens[0]);
+                events.entry(event).or_default().push(outcome(yes));
+                members
+                    .entry(event)
+                    .or_default()
+                    .insert(market.condition_id.as_str());
+            }
+        }
+        let listed = if events.is_empty() {
+            HashMap::new()
+        } else {
+            event_markets(client, &members).await?
+        };
+        for (event, outcomes) in events {
+            let Some(outcomes) = outcomes.into_iter().collect::<Option<Vec<_>>>() else {
+                continue;
+            };
+            if outcomes.len() < 2 {
+                continue;
+            }
+            let complete = listed.get(event).is_some_and(|listed| {
+                listed.len() == members[event].len()
+                    && listed.iter().all(|id| members[event].contains(id.as_str()))
+            });
+            if !complete {
+                tracing::debug!(event, "outcome set incomplete, only checking SellAll");
+            }
+            found.extend(
+                self.check(event, &outcomes)
+                    .into_iter()
+                    .filter(|o| complete || o.kind == ArbKind::SellAll),
+            );
+        }
+        Ok(found)
+    }
+
+    /// Checks one set of mutually exclusive outcomes, each with its book and fee rate.
+    pub fn check(
+        &self,
+        market: &str,
+        outcomes: &[(Token, SortedBook, u32)],
+    ) -> Vec<ArbOpportunity> {
+        [ArbKind::BuyAll, ArbKind::SellAll]
+            .into_iter()
+            .filter_map(|kind| self.check_kind(market, outcomes, kind))
+            .collect()
+    }
+
+    fn check_kind(
+        &self,
+        market: &str,
+        outcomes: &[(Token, SortedBook, u32)],
+        kind: ArbKind,
+    ) -> Option<ArbOpportunity> {
+        let side = match kind {
+            ArbKind::BuyAll => Side::BUY,
+            ArbKind::SellAll => Side::SELL,
+        };
+        let mut legs = Vec::with_capacity(outcomes.len());
+        let mut all_in = Decimal::ZERO;
+        for (token, book, fee_rate_bps) in outcomes {
+            let top: &OrderSummary = match kind {
+                ArbKind::BuyAll => book.asks().first()?,
+                ArbKind::SellAll => book.bids().first()?,
+            };
+            all_in += effective_price(*fee_rate_bps, side, top.price);
+            legs.push(ArbLeg {
+                token_id: token.token_id.clone(),
+                outcome: token.outcome.clone(),
+                price: top.price,
+                size: top.size,
+            });
+        }
+        let edge = match kind {
+            ArbKind::BuyAll => Decimal::ONE - all_in,
+            ArbKind::SellAll => all_in - Decimal::ONE,
+        };
+        if edge <= self.config.min_edge {
+            return None;
+        }
+        Some(ArbOpportunity {
+            market: market.to_owned(),
+            kind,
+            total_price: legs.iter().map(|l| l.price).sum(),
+            size: legs.iter().map(|l| l.size).min()?,
+            edge,
+            legs,
+        })
+    }
+}
+
+/// Condition IDs of every market `get_markets` lists under each event of `events`. The
+/// pages are only walked when an event is missing from the metadata cache, and every
+/// event found on them is cached.
+async fn event_markets(
+    client: &ClobClient,
+    events: &BTreeMap<&str, BTreeSet<&str>>,
+) -> ClientResult<HashMap<String, BTreeSet<String>>> {
+    let cached: HashMap<String, BTreeSet<String>> = events
+        .keys()
+        .filter_map(|event| Some((event.to_string(), client.metadata.event_markets(event)?)))
+        .collect();
+    if cached.len() == events.len() {
+        return Ok(cached);
+    }
+
+    let mut listed: HashMap<String, BTreeSet<String>> = HashMap::new();
+    let mut next_cursor = INITIAL_CURSOR.to_owned();
+    while next_cursor != END_CURSOR {
+        let page = client.get_markets(Some(&next_cursor)).await?;
+        for market in page.data {
+            if let Some(event) = market.neg_risk_market_id.filter(|_| market.neg_risk) {
+                listed.entry(event).or_default().insert(market.condition_id);
+            }
+        }
+        next_cursor = match page.next_cursor {
+            Some(cursor) if !cursor.is_empty() => cursor,
+            _ => break,
+        };
+    }
+    for event in events.keys() {
+        listed.entry(event.to_string()).or_default();
+    }
+    for (event, markets) in &listed {
+        client.metadata.set_event_markets(event, markets.clone());
+    }
+    listed.retain(|event, _| events.contains_key(event.as_str()));
+    Ok(listed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outcome(token_id: &str, bid: Decimal, size: Decimal) -> (Token, SortedBook, u32) {
+        let token = Token {
+            token_id: token_id.into(),
+            outcome: token_id.into(),
+        };
+        let book = SortedBook::new([OrderSummary { price: bid, size }], []);
+        (token, book, 0)
+    }
+
+    #[test]
+    fn test_buy_all_with_fees() {
+        let ask = |token_id: &str, price: Decimal, fee_rate_bps: u32| {
+            let token = Token {
+                token_id: token_id.into(),
+                outcome: token_id.into(),
+            };
+            let level = OrderSummary {
+                price,
+                size: Decimal::from(10),
+            };
+            (token, SortedBook::new([], [level]), fee_rate_bps)
+        };
+        let outcomes = [
+            ask("yes", Decimal::new(45, 2), 1000),
+            ask("no", Decimal::new(5, 1), 0),
+        ];
+        let found = ArbScanner::default().check("0xm", &outcomes);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].kind, ArbKind::BuyAll);
+        assert_eq!(found[0].total_price, Decimal::new(95, 2));
+        // 1 - 0.95 - 0.045 in fees on the YES leg.
+        assert_eq!(found[0].edge, Decimal::new(5, 3));
+
+        let outcomes = [
+            ask("yes", Decimal::new(45, 2), 2000),
+            ask("no", Decimal::new(5, 1), 0),
+        ];
+        assert!(ArbScanner::default().check("0xm", &outcomes).is_empty());
+    }
+
+    #[test]
+    fn test_sell_all_and_min_edge() {
+        let outcomes = [
+            outcome("yes", Decimal::new(55, 2), Decimal::from(10)),
+            outcome("no", Decimal::new(48, 2), Decimal::from(4)),
+        ];
+        let found = ArbScanner::default().check("0xm", &outcomes);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].kind, ArbKind::SellAll);
+        assert_eq!(found[0].edge, Decimal::new(3, 2));
+        assert_eq!(found[0].size, Decimal::from(4));
+
+        let scanner = ArbScanner::new(ArbScannerConfig {
+            min_edge: Decimal::new(3, 2),
+            ..Default::default()
+        });
+        // The edge has to beat `min_edge`, not just reach it.
+        assert!(scanner.check("0xm", &outcomes).is_empty());
+    }
+}
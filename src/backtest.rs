@@ -0,0 +1,691 @@
+//! Event-driven backtesting against recorded market data.
+//!
+//! [`Backtest::run`] replays [`MarketEvent`]s in timestamp order through a [`Strategy`].
+//! Orders reach the simulated book after a fixed latency and are rounded exactly as
+//! [`ClobClient::create_order`](crate::ClobClient::create_order) would sign them. A
+//! resting order joins the back of the displayed size at its price and only fills
+//! once that queue has traded or been cancelled away, or when the book trades through it.
+
+use crate::fees::calculate_fee;
+use crate::orders::{amounts_to_terms, order_amounts};
+use crate::{ClientResult, OrderArgs, OrderBookSummary, OrderSummary, OrderType, Side, SortedBook};
+use anyhow::anyhow;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+/// A market data update. Timestamps are milliseconds since the Unix epoch, as in
+/// CLOB book snapshots.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MarketEvent {
+    /// A full book snapshot.
+    Book(OrderBookSummary),
+    /// The aggregate size at one price level changed. A size of zero removes the level.
+    PriceChange {
+        asset_id: String,
+        timestamp: u64,
+        side: Side,
+        #[serde(with = "rust_decimal::serde::str")]
+        price: Decimal,
+        #[serde(with = "rust_decimal::serde::str")]
+        size: Decimal,
+    },
+    /// A trade printed. `side` is the side of the aggressing order.
+    Trade {
+        asset_id: String,
+        timestamp: u64,
+        side: Side,
+        #[serde(with = "rust_decimal::serde::str")]
+        price: Decimal,
+        #[serde(with = "rust_decimal::serde::str")]
+        size: Decimal,
+    },
+    /// The last trade price changed, without the size of the trade.
+    LastTradePrice {
+        asset_id: String,
+        timestamp: u64,
+        side: Side,
+        #[serde(with = "rust_decimal::serde::str")]
+        price: Decimal,
+    },
+}
+
+impl MarketEvent {
+    pub fn asset_id(&self) -> &str {
+        match self {
+            MarketEvent::Book(book) => &book.asset_id,
+            MarketEvent::PriceChange { asset_id, .. }
+            | MarketEvent::Trade { asset_id, .. }
+            | MarketEvent::LastTradePrice { asset_id, .. } => asset_id,
+        }
+    }
+
+    pub fn timestamp(&self) -> u64 {
+        match self {
+            MarketEvent::Book(book) => book.timestamp,
+            MarketEvent::PriceChange { timestamp, .. }
+            | MarketEvent::Trade { timestamp, .. }
+            | MarketEvent::LastTradePrice { timestamp, .. } => *timestamp,
+        }
+    }
+}
+
+/// Trading logic driven by a [`Backtest`].
+pub trait Strategy {
+    /// Called after `event` has been applied to the simulated book.
+    fn on_event(&mut self, ctx: &mut BacktestContext, event: &MarketEvent);
+
+    /// Called for each fill, before the event that produced it is passed to `on_event`.
+    fn on_fill(&mut self, _ctx: &mut BacktestContext, _fill: &BacktestFill) {}
+}
+
+#[derive(Debug, Clone)]
+pub struct BacktestConfig {
+    /// Delay between a strategy placing or cancelling an order and it taking effect, in milliseconds.
+    pub latency_ms: u64,
+    /// Tick size used to round order prices and amounts.
+    pub tick_size: Decimal,
+    /// Fee rate charged on every fill.
+    pub fee_rate_bps: u32,
+}
+
+impl Default for BacktestConfig {
+    fn default() -> Self {
+        BacktestConfig {
+            latency_ms: 0,
+            tick_size: Decimal::new(1, 2),
+            fee_rate_bps: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Liquidity {
+    Maker,
+    Taker,
+}
+
+#[derive(Debug, Clone)]
+pub struct BacktestFill {
+    pub order_id: u64,
+    pub asset_id: String,
+    pub side: Side,
+    pub price: Decimal,
+    pub size: Decimal,
+    pub fee: Decimal,
+    pub liquidity: Liquidity,
+    pub timestamp: u64,
+}
+
+/// An order resting in the simulated book.
+#[derive(Debug, Clone)]
+pub struct SimOrder {
+    pub id: u64,
+    pub asset_id: String,
+    pub side: Side,
+    pub price: Decimal,
+    pub size: Decimal,
+    pub filled: Decimal,
+    pub order_type: OrderType,
+    /// Displayed size ahead of this order at its price.
+    pub queue_ahead: Decimal,
+}
+
+impl SimOrder {
+    pub fn remaining(&self) -> Decimal {
+        self.size - self.filled
+    }
+}
+
+/// Position and marked PnL right after a fill.
+#[derive(Debug, Clone)]
+pub struct InventoryPoint {
+    pub timestamp: u64,
+    pub asset_id: String,
+    pub position: Decimal,
+    pub pnl: Decimal,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BacktestReport {
+    pub fills: Vec<BacktestFill>,
+    pub inventory: Vec<InventoryPoint>,
+    pub positions: HashMap<String, Decimal>,
+    pub orders_placed: usize,
+    pub size_placed: Decimal,
+    pub size_filled: Decimal,
+    pub fees: Decimal,
+    /// Cash flow from fills, net of fees.
+    pub cash: Decimal,
+    /// `cash` plus positions marked at the mid, or the last trade price for one-sided books.
+    pub pnl: Decimal,
+}
+
+impl BacktestReport {
+    /// Filled size as a fraction of placed size.
+    pub fn fill_rate(&self) -> Decimal {
+        if self.size_placed.is_zero() {
+            Decimal::ZERO
+        } else {
+            self.size_filled / self.size_placed
+        }
+    }
+}
+
+#[derive(Default)]
+struct SimBook {
+    bids: BTreeMap<Decimal, Decimal>,
+    asks: BTreeMap<Decimal, Decimal>,
+    last_trade: Option<Decimal>,
+}
+
+impl SimBook {
+    fn levels_mut(&mut self, side: Side) -> &mut BTreeMap<Decimal, Decimal> {
+        match side {
+            Side::BUY => &mut self.bids,
+            Side::SELL => &mut self.asks,
+        }
+    }
+
+    fn size_at(&self, side: Side, price: Decimal) -> Decimal {
+        let levels = match side {
+            Side::BUY => &self.bids,
+            Side::SELL => &self.asks,
+        };
+        levels.get(&price).copied().unwrap_or_default()
+    }
+
+    /// Best opposite level an order on `side` at `price` would trade against.
+    fn crossing(&self, side: Side, price: Decimal) -> Option<(Decimal, Decimal)> {
+        match side {
+            Side::BUY => self.asks.iter().next().filter(|(p, _)| **p <= price),
+            Side::SELL => self.bids.iter().next_back().filter(|(p, _)| **p >= price),
+        }
+        .map(|(p, s)| (*p, *s))
+    }
+
+    fn mark(&self) -> Option<Decimal> {
+        match (self.bids.keys().next_back(), self.asks.keys().next()) {
+            (Some(bid), Some(ask)) => Some((bid + ask) / Decimal::TWO),
+            _ => self.last_trade,
+        }
+    }
+}
+
+enum Action {
+    Place(SimOrder),
+    Cancel(u64),
+}
+
+/// Simulator state visible to a [`Strategy`].
+pub struct BacktestContext {
+    config: BacktestConfig,
+    now: u64,
+    next_id: u64,
+    books: HashMap<String, SimBook>,
+    pending: VecDeque<(u64, Action)>,
+    orders: Vec<SimOrder>,
+    new_fills: Vec<BacktestFill>,
+    report: BacktestReport,
+}
+
+impl BacktestContext {
+    fn new(config: BacktestConfig) -> Self {
+        BacktestContext {
+            config,
+            now: 0,
+            next_id: 0,
+            books: HashMap::new(),
+            pending: VecDeque::new(),
+            orders: Vec::new(),
+            new_fills: Vec::new(),
+            report: BacktestReport::default(),
+        }
+    }
+
+    /// Timestamp of the event being processed.
+    pub fn now(&self) -> u64 {
+        self.now
+    }
+
+    pub fn best_bid(&self, asset_id: &str) -> Option<Decimal> {
+        self.books.get(asset_id)?.bids.keys().next_back().copied()
+    }
+
+    pub fn best_ask(&self, asset_id: &str) -> Option<Decimal> {
+        self.books.get(asset_id)?.asks.keys().next().copied()
+    }
+
+    /// The simulated book of `asset_id`, for analytics.
+    pub fn book(&self, asset_id: &str) -> Option<SortedBook> {
+        let book = self.books.get(asset_id)?;
+        let levels = |l: &BTreeMap<Decimal, Decimal>| -> Vec<OrderSummary> {
+            l.iter()
+                .map(|(&price, &size)| OrderSummary { price, size })
+                .collect()
+        };
+        Some(SortedBook::new(levels(&book.bids), levels(&book.asks)))
+    }
+
+    pub fn position(&self, asset_id: &str) -> Decimal {
+        self.report
+            .positions
+            .get(asset_id)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Orders currently resting in the book. Orders still in flight are not included.
+    pub fn open_orders(&self) -> &[SimOrder] {
+        &self.orders
+    }
+
+    /// Places an order, which reaches the book after the configured latency.
+    /// FOK orders are killed unless they fill completely on arrival; all other
+    /// order types rest until filled or cancelled.
+    pub fn place(&mut self, args: &OrderArgs, order_type: OrderType) -> ClientResult<u64> {
+        let (maker, taker) =
+            order_amounts(args.side, args.size, args.price, self.config.tick_size)?;
+        if maker == 0 || taker == 0 {
+            return Err(anyhow!("Order size {} rounds to zero", args.size));
+        }
+        let (price, size) = amounts_to_terms(args.side, maker.into(), taker.into());
+
+        self.next_id += 1;
+        let order = SimOrder {
+            id: self.next_id,
+            asset_id: args.token_id.clone(),
+            side: args.side,
+            price,
+            size,
+            filled: Decimal::ZERO,
+            order_type,
+            queue_ahead: Decimal::ZERO,
+        };
+        self.report.orders_placed += 1;
+        self.report.size_placed += size;
+        self.pending
+            .push_back((self.now + self.config.latency_ms, Action::Place(order)));
+        Ok(self.next_id)
+    }
+
+    /// Cancels an order after the configured latency; it can still fill in the meantime.
+    pub fn cancel(&mut self, order_id: u64) {
+        self.pending
+            .push_back((self.now + self.config.latency_ms, Action::Cancel(order_id)));
+    }
+
+    fn pnl(&self) -> Decimal {
+        self.report.cash
+            + self
+                .report
+                .positions
+                .iter()
+                .filter_map(|(asset_id, position)| {
+                    Some(*position * self.books.get(asset_id)?.mark()?)
+                })
+                .sum::<Decimal>()
+    }
+
+    fn record_fill(
+        &mut self,
+        order: &SimOrder,
+        price: Decimal,
+        size: Decimal,
+        liquidity: Liquidity,
+        timestamp: u64,
+    ) {
+        let fee = calculate_fee(self.config.fee_rate_bps, price, size);
+        let position = self
+            .report
+            .positions
+            .entry(order.asset_id.clone())
+            .or_default();
+        match order.side {
+            Side::BUY => {
+                *position += size;
+                self.report.cash -= price * size;
+            }
+            Side::SELL => {
+                *position -= size;
+                self.report.cash += price * size;
+            }
+        }
+        let position = *position;
+        self.report.cash -= fee;
+        self.report.fees += fee;
+        self.report.size_filled += size;
+
+        let fill = BacktestFill {
+            order_id: order.id,
+            asset_id: order.asset_id.clone(),
+            side: order.side,
+            price,
+            size,
+            fee,
+            liquidity,
+            timestamp,
+        };
+        self.report.fills.push(fill.clone());
+        self.new_fills.push(fill);
+        let pnl = self.pnl();
+        self.report.inventory.push(InventoryPoint {
+            timestamp,
+            asset_id: order.asset_id.clone(),
+            position,
+            pnl,
+        });
+    }
+
+    /// Applies pending actions that take effect at or before `until`.
+    fn activate(&mut self, until: u64) {
+        while self.pending.front().is_some_and(|(at, _)| *at <= until) {
+            let (at, action) = self.pending.pop_front().expect("Checked above");
+            match action {
+                Action::Cancel(id) => self.orders.retain(|o| o.id != id),
+                Action::Place(mut order) => {
+                    let book = self.books.entry(order.asset_id.clone()).or_default();
+                    if order.order_type == OrderType::FOK {
+                        let available: Decimal = match order.side {
+                            Side::BUY => book.asks.range(..=order.price).map(|(_, s)| *s).sum(),
+                            Side::SELL => book.bids.range(order.price..).map(|(_, s)| *s).sum(),
+                        };
+                        if available < order.size {
+                            continue;
+                        }
+                    }
+                    let mut remaining = order.size;
+                    let mut fills = Vec::new();
+                    while remaining > Decimal::ZERO {
+                        let Some((price, size)) = book.crossing(order.side, order.price) else {
+                            break;
+                        };
+                        let matched = size.min(remaining);
+                        take_level(book.levels_mut(opposite(order.side)), price, matched);
+                        remaining -= matched;
+                        fills.push((price, matched));
+                    }
+                    order.queue_ahead = book.size_at(order.side, order.price);
+                    for (price, size) in fills {
+                        self.record_fill(&order, price, size, Liquidity::Taker, at);
+                        order.filled += size;
+                    }
+                    if order.remaining() > Decimal::ZERO
+                        && !matches!(order.order_type, OrderType::FOK | OrderType::FAK)
+                    {
+                        self.orders.push(order);
+                    }
+                }
+            }
+        }
+    }
+
+    fn apply(&mut self, event: &MarketEvent) {
+        let asset_id = event.asset_id();
+        let book = self.books.entry(asset_id.to_owned()).or_default();
+        match event {
+            MarketEvent::Book(summary) => {
+                book.bids = summary.bids.iter().map(|l| (l.price, l.size)).collect();
+                book.asks = summary.asks.iter().map(|l| (l.price, l.size)).collect();
+                for order in self.orders.iter_mut().filter(|o| o.asset_id == asset_id) {
+                    order.queue_ahead =
+                        order.queue_ahead.min(book.size_at(order.side, order.price));
+                }
+            }
+            MarketEvent::PriceChange {
+                side, price, size, ..
+            } => {
+                if size.is_zero() {
+                    book.levels_mut(*side).remove(price);
+                } else {
+                    book.levels_mut(*side).insert(*price, *size);
+                }
+                for order in self
+                    .orders
+                    .iter_mut()
+                    .filter(|o| o.asset_id == asset_id && o.side == *side && o.price == *price)
+                {
+                    order.queue_ahead = order.queue_ahead.min(*size);
+                }
+            }
+            MarketEvent::Trade {
+                side, price, size, ..
+            } => {
+                book.last_trade = Some(*price);
+                self.trade(asset_id, *side, *price, *size);
+            }
+            MarketEvent::LastTradePrice { price, .. } => book.last_trade = Some(*price),
+        }
+        self.cross_resting(asset_id);
+    }
+
+    /// Fills resting orders against a printed trade, after the queue ahead of them.
+    fn trade(&mut self, asset_id: &str, aggressor: Side, price: Decimal, mut size: Decimal) {
+        let mut orders = std::mem::take(&mut self.orders);
+        let mut resting: Vec<_> = orders
+            .iter_mut()
+            .filter(|o| o.asset_id == asset_id && o.side == opposite(aggressor))
+            .filter(|o| match aggressor {
+                Side::BUY => o.price <= price,
+                Side::SELL => o.price >= price,
+            })
+            .collect();
+        // Better-priced orders first, then in placement order.
+        resting.sort_by(|a, b| match aggressor {
+            Side::BUY => a.price.cmp(&b.price).then(a.id.cmp(&b.id)),
+            Side::SELL => b.price.cmp(&a.price).then(a.id.cmp(&b.id)),
+        });
+        for order in resting {
+            if order.price == price {
+                let ahead = order.queue_ahead.min(size);
+                order.queue_ahead -= ahead;
+                size -= ahead;
+            }
+            let matched = order.remaining().min(size);
+            if matched.is_zero() {
+                continue;
+            }
+            size -= matched;
+            self.record_fill(order, order.price, matched, Liquidity::Maker, self.now);
+            order.filled += matched;
+        }
+        orders.retain(|o| o.remaining() > Decimal::ZERO);
+        self.orders = orders;
+    }
+
+    /// Fills resting orders that the book has moved through, at the order price.
+    fn cross_resting(&mut self, asset_id: &str) {
+        let mut orders = std::mem::take(&mut self.orders);
+        for order in orders.iter_mut().filter(|o| o.asset_id == asset_id) {
+            while order.remaining() > Decimal::ZERO {
+                let book = self.books.get_mut(asset_id).expect("Book exists");
+                let Some((level, size)) = book.crossing(order.side, order.price) else {
+                    break;
+                };
+                let matched = size.min(order.remaining());
+                take_level(book.levels_mut(opposite(order.side)), level, matched);
+                self.record_fill(order, order.price, matched, Liquidity::Maker, self.now);
+                order.filled += matched;
+            }
+        }
+        orders.retain(|o| o.remaining() > Decimal::ZERO);
+        self.orders = orders;
+    }
+}
+
+fn opposite(side: Side) -> Side {
+    match side {
+        Side::BUY => Side::SELL,
+        Side::SELL => Side::BUY,
+    }
+}
+
+fn take_level(levels: &mut BTreeMap<Decimal, Decimal>, price: Decimal, size: Decimal) {
+    if let Some(level) = levels.get_mut(&price) {
+        *level -= size;
+        if level.is_zero() {
+            levels.remove(&price);
+        }
+    }
+}
+
+/// Replays market data through a [`Strategy`] and simulates its orders.
+pub struct Backtest {
+    config: BacktestConfig,
+}
+
+impl Backtest {
+    pub fn new(config: BacktestConfig) -> Self {
+        Backtest { config }
+    }
+
+    /// Runs `strategy` over `events`, which are sorted by timestamp first. Actions that
+    /// would take effect after the last event are dropped.
+    pub fn run<S: Strategy + ?Sized>(
+        &self,
+        events: impl IntoIterator<Item = MarketEvent>,
+        strategy: &mut S,
+    ) -> BacktestReport {
+        let mut events: Vec<_> = events.into_iter().collect();
+        events.sort_by_key(MarketEvent::timestamp);
+
+        let mut ctx = BacktestContext::new(self.config.clone());
+        for event in &events {
+            ctx.activate(event.timestamp());
+            ctx.now = event.timestamp();
+            ctx.apply(event);
+            for fill in std::mem::take(&mut ctx.new_fills) {
+                strategy.on_fill(&mut ctx, &fill);
+            }
+            strategy.on_event(&mut ctx, event);
+        }
+        ctx.report.pnl = ctx.pnl();
+        ctx.report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::OrderSummary;
+    use std::str::FromStr;
+
+    fn dec(s: &str) -> Decimal {
+        Decimal::from_str(s).unwrap()
+    }
+
+    fn book(timestamp: u64, bids: &[(&str, &str)], asks: &[(&str, &str)]) -> MarketEvent {
+        let levels = |l: &[(&str, &str)]| {
+            l.iter()
+                .map(|(p, s)| OrderSummary {
+                    price: dec(p),
+                    size: dec(s),
+                })
+                .collect()
+        };
+        MarketEvent::Book(OrderBookSummary {
+            market: "0xmarket".into(),
+            asset_id: "1".into(),
+            hash: String::new(),
+            timestamp,
+            bids: levels(bids),
+            asks: levels(asks),
+        })
+    }
+
+    fn trade(timestamp: u64, side: Side, price: &str, size: &str) -> MarketEvent {
+        MarketEvent::Trade {
+            asset_id: "1".into(),
+            timestamp,
+            side,
+            price: dec(price),
+            size: dec(size),
+        }
+    }
+
+    /// Places one order on the first event.
+    struct PlaceOnce {
+        order: Option<(OrderArgs, OrderType)>,
+        fills: Vec<BacktestFill>,
+    }
+
+    impl PlaceOnce {
+        fn new(side: Side, price: &str, size: &str, order_type: OrderType) -> Self {
+            PlaceOnce {
+                order: Some((OrderArgs::new("1", dec(price), dec(size), side), order_type)),
+                fills: Vec::new(),
+            }
+        }
+    }
+
+    impl Strategy for PlaceOnce {
+        fn on_event(&mut self, ctx: &mut BacktestContext, _event: &MarketEvent) {
+            if let Some((args, order_type)) = self.order.take() {
+                ctx.place(&args, order_type).unwrap();
+            }
+        }
+
+        fn on_fill(&mut self, _ctx: &mut BacktestContext, fill: &BacktestFill) {
+            self.fills.push(fill.clone());
+        }
+    }
+
+    #[test]
+    fn test_backtest_fills_after_queue_ahead_trades() {
+        let mut strategy = PlaceOnce::new(Side::BUY, "0.5", "10", OrderType::GTC);
+        let report = Backtest::new(BacktestConfig {
+            fee_rate_bps: 100,
+            ..Default::default()
+        })
+        .run(
+            [
+                trade(3_000, Side::SELL, "0.5", "50"),
+                book(1_000, &[("0.5", "100")], &[("0.52", "100")]),
+                trade(2_000, Side::SELL, "0.5", "60"),
+            ],
+            &mut strategy,
+        );
+
+        assert_eq!(strategy.fills.len(), 1);
+        let fill = &strategy.fills[0];
+        assert_eq!((fill.timestamp, fill.size), (3_000, dec("10")));
+        assert_eq!(fill.liquidity, Liquidity::Maker);
+        assert_eq!(report.fill_rate(), Decimal::ONE);
+        assert_eq!(report.positions["1"], dec("10"));
+        assert_eq!(report.fees, dec("0.05"));
+        // Bought 10 at 0.50, marked at the 0.51 mid, less fees.
+        assert_eq!(report.pnl, dec("0.05"));
+        assert_eq!(report.inventory.len(), 1);
+    }
+
+    #[test]
+    fn test_backtest_latency_and_rounding() {
+        let mut strategy = PlaceOnce::new(Side::BUY, "0.523", "10", OrderType::FOK);
+        let report = Backtest::new(BacktestConfig {
+            latency_ms: 100,
+            ..Default::default()
+        })
+        .run(
+            [
+                book(0, &[("0.5", "10")], &[("0.52", "5")]),
+                MarketEvent::PriceChange {
+                    asset_id: "1".into(),
+                    timestamp: 50,
+                    side: Side::SELL,
+                    price: dec("0.52"),
+                    size: dec("20"),
+                },
+                trade(150, Side::BUY, "0.6", "1"),
+            ],
+            &mut strategy,
+        );
+
+        // The FOK arrives at t=100, after the ask was replenished, at the rounded price.
+        assert_eq!(report.fills.len(), 1);
+        assert_eq!(report.fills[0].price, dec("0.52"));
+        assert_eq!(report.fills[0].timestamp, 100);
+        assert_eq!(report.fills[0].liquidity, Liquidity::Taker);
+        assert_eq!(report.positions["1"], dec("10"));
+    }
+}
@@ -0,0 +1,313 @@
+//! Order book analytics on levels sorted best first.
+
+use crate::{OrderBookSummary, OrderSummary, Side, Token};
+use rust_decimal::Decimal;
+use std::collections::BTreeMap;
+
+/// The result of a taker order that sweeps the book up to and including one level.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImpactPoint {
+    /// Shares filled.
+    pub size: Decimal,
+    pub average_price: Decimal,
+    /// Price of the last level taken.
+    pub worst_price: Decimal,
+    /// Distance of the average price from the best price.
+    pub impact: Decimal,
+}
+
+/// A book with duplicate prices merged, empty levels dropped, bids sorted from the highest
+/// price and asks from the lowest.
+///
+/// Methods taking a `side` describe a taker order on that side: `BUY` takes the asks.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SortedBook {
+    bids: Vec<OrderSummary>,
+    asks: Vec<OrderSummary>,
+}
+
+impl SortedBook {
+    pub fn new(
+        bids: impl IntoIterator<Item = OrderSummary>,
+        asks: impl IntoIterator<Item = OrderSummary>,
+    ) -> Self {
+        SortedBook {
+            bids: merge(bids, true),
+            asks: merge(asks, false),
+        }
+    }
+
+    pub fn bids(&self) -> &[OrderSummary] {
+        &self.bids
+    }
+
+    pub fn asks(&self) -> &[OrderSummary] {
+        &self.asks
+    }
+
+    pub fn best_bid(&self) -> Option<Decimal> {
+        self.bids.first().map(|l| l.price)
+    }
+
+    pub fn best_ask(&self) -> Option<Decimal> {
+        self.asks.first().map(|l| l.price)
+    }
+
+    pub fn midpoint(&self) -> Option<Decimal> {
+        Some((self.best_bid()? + self.best_ask()?) / Decimal::TWO)
+    }
+
+    pub fn spread(&self) -> Option<Decimal> {
+        Some(self.best_ask()? - self.best_bid()?)
+    }
+
+    /// The spread in ticks, or `None` for an empty side or a zero `tick_size`.
+    pub fn spread_ticks(&self, tick_size: Decimal) -> Option<Decimal> {
+        self.spread()?.checked_div(tick_size)
+    }
+
+    /// Midpoint weighted by the opposite size at the top of book, so it leans towards the
+    /// side more likely to be traded through.
+    pub fn microprice(&self) -> Option<Decimal> {
+        let (bid, ask) = (self.bids.first()?, self.asks.first()?);
+        let total = bid.size + ask.size;
+        Some((bid.price * ask.size + ask.price * bid.size) / total)
+    }
+
+    /// Bid size priced within `ticks` ticks of the best bid.
+    pub fn bid_depth_within(&self, ticks: u32, tick_size: Decimal) -> Decimal {
+        depth_within(&self.bids, ticks, tick_size)
+    }
+
+    /// Ask size priced within `ticks` ticks of the best ask.
+    pub fn ask_depth_within(&self, ticks: u32, tick_size: Decimal) -> Decimal {
+        depth_within(&self.asks, ticks, tick_size)
+    }
+
+    /// Average price a taker order on `side` pays to fill `size` shares, or `None` when the
+    /// book is too thin.
+    pub fn vwap(&self, side: Side, size: Decimal) -> Option<Decimal> {
+        if size <= Decimal::ZERO {
+            return None;
+        }
+        let mut remaining = size;
+        let mut notional = Decimal::ZERO;
+        for level in self.taken(side) {
+            let take = level.size.min(remaining);
+            notional += take * level.price;
+            remaining -= take;
+            if remaining.is_zero() {
+                return Some(notional / size);
+            }
+        }
+        None
+    }
+
+    /// Fill size, average price and impact of a taker order on `side` that sweeps one more
+    /// level at each point.
+    pub fn impact_curve(&self, side: Side) -> Vec<ImpactPoint> {
+        let levels = self.taken(side);
+        let Some(best) = levels.first().map(|l| l.price) else {
+            return Vec::new();
+        };
+        let mut size = Decimal::ZERO;
+        let mut notional = Decimal::ZERO;
+        levels
+            .iter()
+            .map(|level| {
+                size += level.size;
+                notional += level.size * level.price;
+                let average_price = notional / size;
+                ImpactPoint {
+                    size,
+                    average_price,
+                    worst_price: level.price,
+                    impact: (average_price - best).abs(),
+                }
+            })
+            .collect()
+    }
+
+    /// `(bid size - ask size) / (bid size + ask size)` over the top `levels` levels of each
+    /// side: 1 when only bids rest, -1 when only asks do.
+    pub fn imbalance(&self, levels: usize) -> Option<Decimal> {
+        let depth =
+            |side: &[OrderSummary]| -> Decimal { side.iter().take(levels).map(|l| l.size).sum() };
+        let (bids, asks) = (depth(&self.bids), depth(&self.asks));
+        let total = bids + asks;
+        (!total.is_zero()).then(|| (bids - asks) / total)
+    }
+
+    /// The book of the other outcome of a binary market seen in this outcome's prices: a
+    /// bid at `p` becomes an ask at `1 - p`, and an ask at `p` a bid at `1 - p`.
+    pub fn complement(&self) -> SortedBook {
+        let mirror = |levels: &[OrderSummary]| -> Vec<OrderSummary> {
+            levels
+                .iter()
+                .map(|l| OrderSummary {
+                    price: Decimal::ONE - l.price,
+                    size: l.size,
+                })
+                .collect()
+        };
+        SortedBook::new(mirror(&self.asks), mirror(&self.bids))
+    }
+
+    /// Both books' levels, with sizes at equal prices added together.
+    pub fn merge(&self, other: &SortedBook) -> SortedBook {
+        SortedBook::new(
+            self.bids.iter().chain(&other.bids).cloned(),
+            self.asks.iter().chain(&other.asks).cloned(),
+        )
+    }
+
+    /// Levels a taker order on `side` trades against.
+    fn taken(&self, side: Side) -> &[OrderSummary] {
+        match side {
+            Side::BUY => &self.asks,
+            Side::SELL => &self.bids,
+        }
+    }
+}
+
+/// Size of `levels`, best first, priced within `ticks` ticks of the first level.
+fn depth_within(levels: &[OrderSummary], ticks: u32, tick_size: Decimal) -> Decimal {
+    let Some(best) = levels.first().map(|l| l.price) else {
+        return Decimal::ZERO;
+    };
+    let range = tick_size * Decimal::from(ticks);
+    levels
+        .iter()
+        .take_while(|l| (l.price - best).abs() <= range)
+        .map(|l| l.size)
+        .sum()
+}
+
+fn merge(levels: impl IntoIterator<Item = OrderSummary>, descending: bool) -> Vec<OrderSummary> {
+    let mut merged = BTreeMap::<Decimal, Decimal>::new();
+    for level in levels {
+        if level.size > Decimal::ZERO {
+            *merged.entry(level.price).or_default() += level.size;
+        }
+    }
+    let levels = merged
+        .into_iter()
+        .map(|(price, size)| OrderSummary { price, size });
+    if descending {
+        levels.rev().collect()
+    } else {
+        levels.collect()
+    }
+}
+
+/// One outcome of a binary market priced with the liquidity of both tokens.
+#[derive(Debug, Clone)]
+pub struct SyntheticBook {
+    pub token_id: String,
+    pub outcome: String,
+    /// The token's own book.
+    pub direct: SortedBook,
+    /// The other token's book in this token's prices.
+    pub implied: SortedBook,
+    /// `direct` and `implied` merged: the best prices available through either token.
+    pub combined: SortedBook,
+}
+
+impl SyntheticBook {
+    /// Prices `token` from its own book and the book of the other outcome.
+    pub fn new(token: &Token, book: &SortedBook, other: &SortedBook) -> Self {
+        let implied = other.complement();
+        SyntheticBook {
+            token_id: token.token_id.clone(),
+            outcome: token.outcome.clone(),
+            combined: book.merge(&implied),
+            direct: book.clone(),
+            implied,
+        }
+    }
+}
+
+impl From<&OrderBookSummary> for SortedBook {
+    fn from(book: &OrderBookSummary) -> Self {
+        SortedBook::new(book.bids.iter().cloned(), book.asks.iter().cloned())
+    }
+}
+
+impl OrderBookSummary {
+    pub fn sorted(&self) -> SortedBook {
+        SortedBook::from(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn dec(s: &str) -> Decimal {
+        Decimal::from_str(s).unwrap()
+    }
+
+    fn level(price: &str, size: &str) -> OrderSummary {
+        OrderSummary {
+            price: dec(price),
+            size: dec(size),
+        }
+    }
+
+    fn book() -> SortedBook {
+        SortedBook::new(
+            [level("0.40", "30"), level("0.45", "10"), level("0.39", "0")],
+            [
+                level("0.60", "20"),
+                level("0.50", "10"),
+                level("0.50", "20"),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_sorted_book_top_of_book() {
+        let book = book();
+        assert_eq!(book.bids(), &[level("0.45", "10"), level("0.40", "30")]);
+        assert_eq!(book.asks()[0], level("0.50", "30"));
+        assert_eq!(book.spread_ticks(dec("0.01")), Some(dec("5")));
+        // (0.45 * 30 + 0.5 * 10) / 40
+        assert_eq!(book.microprice(), Some(dec("0.4625")));
+        assert_eq!(book.spread_ticks(Decimal::ZERO), None);
+        assert_eq!(book.bid_depth_within(5, dec("0.01")), dec("40"));
+        assert_eq!(book.bid_depth_within(4, dec("0.01")), dec("10"));
+        assert_eq!(book.ask_depth_within(10, dec("0.01")), dec("50"));
+        assert_eq!(book.imbalance(1), Some(dec("-0.5")));
+    }
+
+    #[test]
+    fn test_synthetic_book() {
+        let yes = SortedBook::new([level("0.45", "10")], [level("0.60", "10")]);
+        let no = SortedBook::new([level("0.42", "5")], [level("0.50", "20")]);
+        let token = Token {
+            token_id: "yes".into(),
+            outcome: "Yes".into(),
+        };
+        let synthetic = SyntheticBook::new(&token, &yes, &no);
+        assert_eq!(synthetic.implied.bids(), &[level("0.50", "20")]);
+        assert_eq!(synthetic.implied.asks(), &[level("0.58", "5")]);
+        assert_eq!(synthetic.combined.best_bid(), Some(dec("0.50")));
+        assert_eq!(synthetic.combined.best_ask(), Some(dec("0.58")));
+        assert_eq!(synthetic.combined.asks().len(), 2);
+    }
+
+    #[test]
+    fn test_sorted_book_vwap_and_impact() {
+        let book = book();
+        assert_eq!(book.vwap(Side::BUY, dec("40")), Some(dec("0.525")));
+        assert_eq!(book.vwap(Side::SELL, dec("20")), Some(dec("0.425")));
+        assert_eq!(book.vwap(Side::BUY, dec("51")), None);
+
+        let curve = book.impact_curve(Side::BUY);
+        assert_eq!(curve.len(), 2);
+        assert_eq!(curve[1].size, dec("50"));
+        assert_eq!(curve[1].average_price, dec("0.54"));
+        assert_eq!(curve[1].impact, dec("0.04"));
+    }
+}
@@ -0,0 +1,359 @@
+//! Record-and-replay of HTTP traffic for offline, deterministic tests.
+//!
+//! [`Recorder`] appends every request/response pair that passes through it to a
+//! newline-delimited JSON file, with auth headers and returned credentials scrubbed. [`Replayer`] serves
+//! those responses back without touching the network.
+
+use crate::headers::SENSITIVE_HEADERS;
+use crate::{ClientResult, Middleware, Next};
+use anyhow::{anyhow, Context};
+use async_trait::async_trait;
+use reqwest::{Request, Response};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+const REDACTED: &str = "[REDACTED]";
+
+/// JSON fields holding credentials in response bodies, e.g. from `/auth/api-key`.
+const SENSITIVE_FIELDS: [&str; 2] = ["secret", "passphrase"];
+
+/// A single recorded request/response pair.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interaction {
+    pub method: String,
+    /// Path and query, without the host.
+    pub path: String,
+    #[serde(default)]
+    pub request_headers: BTreeMap<String, String>,
+    pub request_body: Option<String>,
+    pub status: u16,
+    #[serde(default)]
+    pub response_headers: BTreeMap<String, String>,
+    pub response_body: String,
+}
+
+/// Masks [`SENSITIVE_FIELDS`] anywhere in a JSON body. Non-JSON bodies and bodies
+/// without such fields are returned unchanged.
+fn redact_response_body(body: &[u8]) -> String {
+    fn redact(value: &mut serde_json::Value) -> bool {
+        match value {
+            serde_json::Value::Object(map) => {
+                let mut changed = false;
+                for (k, v) in map.iter_mut() {
+                    if SENSITIVE_FIELDS.contains(&k.as_str()) {
+                        *v = serde_json::Value::String(REDACTED.to_owned());
+                        changed = true;
+                    } else {
+                        changed |= redact(v);
+                    }
+                }
+                changed
+            }
+            serde_json::Value::Array(items) => items.iter_mut().fold(false, |c, v| redact(v) | c),
+            _ => false,
+        }
+    }
+
+    if let Ok(mut value) = serde_json::from_slice::<serde_json::Value>(body) {
+        if redact(&mut value) {
+            return value.to_string();
+        }
+    }
+    String::from_utf8_lossy(body).into_owned()
+}
+
+fn path_and_query(req: &Request) -> String {
+    match req.url().query() {
+        Some(q) => format!("{}?{q}", req.url().path()),
+        None => req.url().path().to_owned(),
+    }
+}
+
+/// Middleware that records traffic to a cassette file. Add it before any middleware
+/// whose effect should be captured in the recording.
+pub struct Recorder {
+    file: Mutex<File>,
+}
+
+impl Recorder {
+    /// Opens `path` for appending, creating it if needed.
+    pub fn create(path: impl AsRef<Path>) -> ClientResult<Self> {
+        let path = path.as_ref();
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open cassette {}", path.display()))?;
+        Ok(Recorder {
+            file: Mutex::new(file),
+        })
+    }
+}
+
+#[async_trait]
+impl Middleware for Recorder {
+    async fn handle(&self, req: Request, next: Next<'_>) -> ClientResult<Response> {
+        let mut secrets = Vec::new();
+        let mut request_headers = BTreeMap::new();
+        for (name, value) in req.headers() {
+            let value = value.to_str().unwrap_or_default();
+            if SENSITIVE_HEADERS.contains(&name.as_str()) {
+                if !value.is_empty() {
+                    secrets.push(value.to_owned());
+                }
+                request_headers.insert(name.to_string(), REDACTED.to_owned());
+            } else {
+                request_headers.insert(name.to_string(), value.to_owned());
+            }
+        }
+        // The API key also appears as the `owner` of posted orders.
+        let request_body = req.body().and_then(|b| b.as_bytes()).map(|b| {
+            secrets
+                .iter()
+                .fold(String::from_utf8_lossy(b).into_owned(), |body, s| {
+                    body.replace(s.as_str(), REDACTED)
+                })
+        });
+        let method = req.method().to_string();
+        let path = path_and_query(&req);
+
+        let resp = next.run(req).await?;
+        let status = resp.status();
+        let headers = resp.headers().clone();
+        let body = resp.bytes().await?;
+
+        let interaction = Interaction {
+            method,
+            path,
+            request_headers,
+            request_body,
+            status: status.as_u16(),
+            response_headers: headers
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or_default().to_owned()))
+                .collect(),
+            response_body: redact_response_body(&body),
+        };
+        let line = serde_json::to_string(&interaction)?;
+        {
+            let mut file = self.file.lock().expect("Cassette lock poisoned");
+            writeln!(file, "{line}").context("Failed to write cassette")?;
+        }
+
+        let mut builder = http::Response::builder().status(status);
+        for (k, v) in headers.iter() {
+            builder = builder.header(k, v);
+        }
+        Ok(builder.body(body)?.into())
+    }
+}
+
+/// Middleware that answers requests from a recording instead of the network.
+///
+/// Requests are matched on method, path and query; repeated requests are answered
+/// with the recorded responses in order. A request with no recording left fails.
+/// Add it as the last middleware.
+pub struct Replayer {
+    interactions: Mutex<HashMap<(String, String), VecDeque<Interaction>>>,
+}
+
+impl Replayer {
+    pub fn open(path: impl AsRef<Path>) -> ClientResult<Self> {
+        let path = path.as_ref();
+        let file = File::open(path)
+            .with_context(|| format!("Failed to open cassette {}", path.display()))?;
+
+        let mut interactions = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            interactions.push(serde_json::from_str(&line).context("Malformed cassette entry")?);
+        }
+        Ok(Self::from_interactions(interactions))
+    }
+
+    pub fn from_interactions(interactions: impl IntoIterator<Item = Interaction>) -> Self {
+        let mut map: HashMap<_, VecDeque<_>> = HashMap::new();
+        for i in interactions {
+            map.entry((i.method.clone(), i.path.clone()))
+                .or_default()
+                .push_back(i);
+        }
+        Replayer {
+            interactions: Mutex::new(map),
+        }
+    }
+}
+
+#[async_trait]
+impl Middleware for Replayer {
+    async fn handle(&self, req: Request, _next: Next<'_>) -> ClientResult<Response> {
+        let key = (req.method().to_string(), path_and_query(&req));
+        let interaction = self
+            .interactions
+            .lock()
+            .expect("Cassette lock poisoned")
+            .get_mut(&key)
+            .and_then(|q| q.pop_front())
+            .ok_or_else(|| anyhow!("No recorded response for {} {}", key.0, key.1))?;
+
+        let mut builder = http::Response::builder().status(interaction.status);
+        for (k, v) in &interaction.response_headers {
+            builder = builder.header(k, v);
+        }
+        Ok(builder.body(interaction.response_body)?.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ApiCreds, ClobClient, MarketOrderArgs, OrderType};
+    use rust_decimal::Decimal;
+
+    const TEST_KEY: &str = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+    fn interaction(method: &str, path: &str, status: u16, body: &str) -> Interaction {
+        Interaction {
+            method: method.to_owned(),
+            path: path.to_owned(),
+            request_headers: BTreeMap::new(),
+            request_body: None,
+            status,
+            response_headers: BTreeMap::new(),
+            response_body: body.to_owned(),
+        }
+    }
+
+    fn client(middleware: impl Middleware + 'static) -> ClobClient {
+        let mut client = ClobClient::with_l2_headers(
+            "http://replay.invalid",
+            TEST_KEY,
+            137,
+            ApiCreds::new(
+                "00000000-api-key",
+                "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=",
+                "passphrase-value",
+            ),
+        );
+        client.add_middleware(middleware);
+        client
+    }
+
+    fn order(id: &str) -> String {
+        format!(r#"{{"id": "{id}", "status": "LIVE", "price": "0.5", "original_size": "10"}}"#)
+    }
+
+    #[tokio::test]
+    async fn test_replay_get_orders_pagination() {
+        let client = client(Replayer::from_interactions([
+            interaction(
+                "GET",
+                "/data/orders?next_cursor=MA%3D%3D",
+                200,
+                &format!(r#"{{"next_cursor": "MTAw", "data": [{}]}}"#, order("a")),
+            ),
+            interaction(
+                "GET",
+                "/data/orders?next_cursor=MTAw",
+                200,
+                &format!(
+                    r#"{{"next_cursor": "LTE=", "data": [{}, {}]}}"#,
+                    order("b"),
+                    order("c")
+                ),
+            ),
+        ]));
+
+        let orders = client.get_orders(None, None).await.unwrap();
+        let ids: Vec<_> = orders.iter().map(|o| o.id.as_str()).collect();
+        assert_eq!(ids, ["a", "b", "c"]);
+    }
+
+    #[tokio::test]
+    async fn test_replay_post_order_error_and_market_price() {
+        let book = r#"{"market": "0xm", "asset_id": "1", "hash": "h", "timestamp": "1",
+            "bids": [], "asks": [{"price": "0.55", "size": "100"}]}"#;
+        let market = r#"{"condition_id": "0xm", "tokens": [{"token_id": "1", "outcome": "Yes"},
+            {"token_id": "2", "outcome": "No"}], "rewards": {"rates": null, "min_size": 0,
+            "max_spread": 0}, "min_incentive_size": null, "max_incentive_spread": null,
+            "active": true, "closed": false, "question_id": "", "minimum_order_size": 5,
+            "minimum_tick_size": 0.01, "description": "", "question": "", "market_slug": "",
+            "seconds_delay": 0, "icon": "", "fpmm": ""}"#;
+        let client = client(Replayer::from_interactions([
+            interaction(
+                "GET",
+                "/tick-size?token_id=1",
+                200,
+                r#"{"minimum_tick_size": 0.01}"#,
+            ),
+            interaction("GET", "/neg-risk?token_id=1", 200, r#"{"neg_risk": false}"#),
+            interaction("GET", "/book?token_id=1", 200, book),
+            interaction("GET", "/book?token_id=1", 200, book),
+            interaction("GET", "/markets/0xm", 200, market),
+            interaction("GET", "/fee-rate?token_id=1", 200, r#"{"base_fee": 0}"#),
+            interaction(
+                "POST",
+                "/order",
+                400,
+                r#"{"error": "not enough balance / allowance"}"#,
+            ),
+        ]));
+
+        let args = MarketOrderArgs::new("1", Decimal::new(10, 0));
+        let order = client.create_market_order(&args, None, None).await.unwrap();
+        assert_eq!(order.maker_amount, "10000000");
+
+        let err = client.post_order(order, OrderType::FOK).await.unwrap_err();
+        assert!(err.to_string().contains("not enough balance"));
+    }
+
+    struct Canned;
+
+    #[async_trait]
+    impl Middleware for Canned {
+        async fn handle(&self, req: Request, _next: Next<'_>) -> ClientResult<Response> {
+            let body = if req.url().path().starts_with("/auth/") {
+                r#"{"apiKey": "derived-key", "secret": "c2VjcmV0LXZhbHVl", "passphrase": "derived-pass"}"#
+            } else {
+                r#"{"canceled": ["a"], "not_canceled": {}}"#
+            };
+            Ok(http::Response::builder().status(200).body(body)?.into())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_record_scrubs_secrets_and_replays() {
+        let path =
+            std::env::temp_dir().join(format!("polymarket-cassette-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let mut recording = client(Recorder::create(&path).unwrap());
+        recording.add_middleware(Canned);
+        let live = recording.cancel("a").await.unwrap();
+        let derived = recording.derive_api_key(None).await.unwrap();
+        assert_eq!(derived.secret.expose_secret(), "c2VjcmV0LXZhbHVl");
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(!contents.contains("passphrase-value"));
+        assert!(!contents.contains("00000000-api-key"));
+        assert!(contents.contains(r#""poly_address""#));
+        assert!(!contents.contains("c2VjcmV0LXZhbHVl"));
+        assert!(!contents.contains("derived-pass"));
+        assert!(contents.contains("derived-key"));
+
+        let replayed = client(Replayer::open(&path).unwrap())
+            .cancel("a")
+            .await
+            .unwrap();
+        assert_eq!(live, replayed);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,11 +1,13 @@
 use crate::Decimal;
 
+use crate::Secret;
 use crate::SignedOrderRequest;
 use alloy_primitives::U256;
 use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::Value;
 use std::fmt::Display;
 use std::str::FromStr;
+use std::time::Duration;
 
 const ZERO_ADDRESS: &str = "0x0000000000000000000000000000000000000000";
 
@@ -146,6 +148,106 @@ impl OpenOrderParams {
     }
 }
 
+/// A trade from `get_trades` or the user channel.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Trade {
+    pub id: String,
+    #[serde(default)]
+    pub taker_order_id: String,
+    #[serde(default)]
+    pub market: String,
+    pub asset_id: String,
+    pub side: Side,
+    #[serde(with = "rust_decimal::serde::str")]
+    pub size: Decimal,
+    #[serde(with = "rust_decimal::serde::str")]
+    pub price: Decimal,
+    #[serde(default, deserialize_with = "deserialize_optional_number_from_string")]
+    pub fee_rate_bps: Option<u32>,
+    #[serde(default)]
+    pub status: String,
+    #[serde(
+        default,
+        alias = "matchtime",
+        deserialize_with = "deserialize_optional_number_from_string"
+    )]
+    pub match_time: Option<u64>,
+    pub outcome: Option<String>,
+    pub owner: Option<String>,
+    pub maker_address: Option<String>,
+    /// `TAKER` or `MAKER`, from the point of view of the API key owner.
+    pub trader_side: Option<String>,
+    #[serde(default)]
+    pub maker_orders: Vec<MakerOrder>,
+}
+
+/// A resting order filled by a [`Trade`].
+#[derive(Debug, Clone, Deserialize)]
+pub struct MakerOrder {
+    pub order_id: String,
+    #[serde(with = "rust_decimal::serde::str")]
+    pub matched_amount: Decimal,
+    #[serde(with = "rust_decimal::serde::str")]
+    pub price: Decimal,
+    pub asset_id: Option<String>,
+    pub side: Option<Side>,
+    pub owner: Option<String>,
+    pub maker_address: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_optional_number_from_string")]
+    pub fee_rate_bps: Option<u32>,
+    pub outcome: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum OrderEventType {
+    Placement,
+    Update,
+    Cancellation,
+}
+
+/// An order update from the user channel.
+#[derive(Debug, Clone, Deserialize)]
+pub struct UserOrderEvent {
+    pub id: String,
+    pub asset_id: String,
+    #[serde(default)]
+    pub market: String,
+    pub side: Side,
+    #[serde(with = "rust_decimal::serde::str")]
+    pub price: Decimal,
+    #[serde(with = "rust_decimal::serde::str")]
+    pub original_size: Decimal,
+    #[serde(with = "rust_decimal::serde::str")]
+    pub size_matched: Decimal,
+    #[serde(rename = "type")]
+    pub event_type: OrderEventType,
+    #[serde(default, deserialize_with = "deserialize_optional_number_from_string")]
+    pub timestamp: Option<u64>,
+}
+
+/// A message from the authenticated user channel.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "event_type", rename_all = "lowercase")]
+pub enum UserEvent {
+    Order(UserOrderEvent),
+    Trade(Trade),
+}
+
+/// A `tick_size_change` message from the market channel.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TickSizeChange {
+    pub asset_id: String,
+    #[serde(default)]
+    pub market: String,
+    #[serde(with = "rust_decimal::serde::str")]
+    pub old_tick_size: Decimal,
+    #[serde(with = "rust_decimal::serde::str")]
+    pub new_tick_size: Decimal,
+    #[serde(default, deserialize_with = "deserialize_optional_number_from_string")]
+    pub timestamp: Option<u64>,
+}
+
 fn deserialize_number_from_string<'de, T, D>(deserializer: D) -> Result<T, D::Error>
 where
     D: Deserializer<'de>,
@@ -165,7 +267,9 @@ where
     }
 }
 
-fn deserialize_optional_number_from_string<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+fn deserialize_optional_number_from_string<'de, T, D>(
+    deserializer: D,
+) -> Result<Option<T>, D::Error>
 where
     D: Deserializer<'de>,
     T: FromStr + serde::Deserialize<'de>,
@@ -198,11 +302,14 @@ pub struct PostOrder {
     order: SignedOrderRequest,
     owner: String,
     order_type: OrderType,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    post_only: bool,
 }
 
 impl PostOrder {
     pub fn new(order: SignedOrderRequest, owner: String, order_type: OrderType) -> Self {
         PostOrder {
+            post_only: order.post_only,
             order,
             owner,
             order_type,
@@ -216,9 +323,16 @@ pub struct OrderArgs {
     pub price: Decimal,
     pub size: Decimal,
     pub side: Side,
+    pub time_in_force: TimeInForce,
+    /// Only rest on the book: the order is refused rather than take liquidity.
+    ///
+    /// `create_order` checks this against the cached top of book before signing. Calling
+    /// `post_order` directly with a [`SignedOrderRequest`] skips that guard and relies on
+    /// the exchange's `postOnly` flag alone.
+    pub post_only: bool,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OrderBookSummary {
     pub market: String,
     pub asset_id: String,
@@ -229,13 +343,63 @@ pub struct OrderBookSummary {
     pub asks: Vec<OrderSummary>,
 }
 
-#[derive(Debug)]
+impl OrderBookSummary {
+    pub fn best_bid(&self) -> Option<Decimal> {
+        self.bids.iter().map(|l| l.price).max()
+    }
+
+    pub fn best_ask(&self) -> Option<Decimal> {
+        self.asks.iter().map(|l| l.price).min()
+    }
+}
+
+/// A market buy spending `amount` USDC.
+#[derive(Debug, Default)]
 pub struct MarketOrderArgs {
     pub token_id: String,
     pub amount: Decimal,
+    /// Furthest the worst level filled may be above the best ask.
+    pub max_slippage: Option<Decimal>,
+    /// Highest price the order may fill at.
+    pub worst_price: Option<Decimal>,
 }
 
-#[derive(Debug, Deserialize)]
+impl MarketOrderArgs {
+    pub fn new(token_id: &str, amount: Decimal) -> Self {
+        MarketOrderArgs {
+            token_id: token_id.to_owned(),
+            amount,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_max_slippage(mut self, max_slippage: Decimal) -> Self {
+        self.max_slippage = Some(max_slippage);
+        self
+    }
+
+    pub fn with_worst_price(mut self, worst_price: Decimal) -> Self {
+        self.worst_price = Some(worst_price);
+        self
+    }
+}
+
+/// Expected execution of a market buy against the current book.
+#[derive(Debug, Clone)]
+pub struct MarketOrderQuote {
+    pub best_price: Decimal,
+    /// Price of the last level consumed; the order is signed at this price.
+    pub worst_price: Decimal,
+    pub average_price: Decimal,
+    /// Shares bought.
+    pub size: Decimal,
+    /// USDC spent.
+    pub amount: Decimal,
+    /// Levels consumed, best first, with the shares taken from each.
+    pub levels: Vec<OrderSummary>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct OrderSummary {
     #[serde(with = "rust_decimal::serde::str")]
     pub price: Decimal,
@@ -250,13 +414,26 @@ impl OrderArgs {
             price,
             size,
             side,
+            time_in_force: TimeInForce::GTC,
+            post_only: false,
         }
     }
+
+    pub fn with_time_in_force(mut self, time_in_force: TimeInForce) -> Self {
+        self.time_in_force = time_in_force;
+        self
+    }
+
+    pub fn with_post_only(mut self, post_only: bool) -> Self {
+        self.post_only = post_only;
+        self
+    }
 }
 
 #[derive(Debug)]
 pub struct ExtraOrderArgs {
-    pub fee_rate_bps: u32,
+    /// `None` signs with the token's fee rate from `get_fee_rate_bps`.
+    pub fee_rate_bps: Option<u32>,
     pub nonce: U256,
     pub taker: String,
 }
@@ -264,7 +441,7 @@ pub struct ExtraOrderArgs {
 impl Default for ExtraOrderArgs {
     fn default() -> Self {
         ExtraOrderArgs {
-            fee_rate_bps: 0,
+            fee_rate_bps: None,
             nonce: U256::ZERO,
             taker: ZERO_ADDRESS.into(),
         }
@@ -316,6 +493,7 @@ pub enum OrderType {
     GTC,
     FOK,
     GTD,
+    FAK,
 }
 
 impl OrderType {
@@ -324,10 +502,80 @@ impl OrderType {
             OrderType::GTC => "GTC",
             OrderType::FOK => "FOK",
             OrderType::GTD => "GTD",
+            OrderType::FAK => "FAK",
+        }
+    }
+}
+
+/// When a GTD order stops trading.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Expiry {
+    /// This long after the exchange's current time.
+    After(Duration),
+    /// At a unix timestamp in seconds.
+    At(u64),
+}
+
+/// How long an order may trade. Carried by [`OrderArgs`] and used by `create_order`
+/// for the expiration and by `create_and_post_order` for the [`OrderType`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TimeInForce {
+    /// Rests until cancelled.
+    #[default]
+    GTC,
+    /// Rests until cancelled or the expiry.
+    GTD(Expiry),
+    /// Fills entirely on arrival or not at all.
+    FOK,
+    /// Fills what it can on arrival; the rest is cancelled.
+    FAK,
+}
+
+impl TimeInForce {
+    pub fn order_type(&self) -> OrderType {
+        match self {
+            TimeInForce::GTC => OrderType::GTC,
+            TimeInForce::GTD(_) => OrderType::GTD,
+            TimeInForce::FOK => OrderType::FOK,
+            TimeInForce::FAK => OrderType::FAK,
         }
     }
 }
 
+/// Order of operations in [`ClobClient::replace_order`](crate::ClobClient::replace_order).
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum ReplaceMode {
+    /// Cancel the old order and post the new one only once the cancel is confirmed.
+    /// Never has both orders resting, but leaves a gap with no order.
+    #[default]
+    CancelFirst,
+    /// Post the new order and cancel the old one only if the post succeeded.
+    /// Never leaves a gap, but both orders rest briefly.
+    PostFirst,
+}
+
+/// Combined result of a cancel/replace.
+#[derive(Debug, Clone, Default)]
+pub struct ReplaceOutcome {
+    /// Whether the old order was confirmed cancelled.
+    pub cancelled: bool,
+    pub cancel_error: Option<String>,
+    /// `post_order` response for the new order, if it was accepted.
+    pub posted: Option<Value>,
+    pub post_error: Option<String>,
+}
+
+impl ReplaceOutcome {
+    /// The old order is gone and the new one is on the book.
+    pub fn is_complete(&self) -> bool {
+        self.cancelled && self.posted.is_some()
+    }
+
+    pub fn new_order_id(&self) -> Option<&str> {
+        self.posted.as_ref()?["orderID"].as_str()
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Copy, Hash, Eq, PartialEq)]
 pub enum Side {
     BUY = 0,
@@ -349,12 +597,42 @@ pub struct BookParams {
     pub side: Side,
 }
 
-#[derive(Default, Debug, Serialize, Deserialize)]
+#[derive(Default, Debug, Clone, Deserialize)]
 pub struct ApiCreds {
     #[serde(rename = "apiKey")]
     pub api_key: String,
-    pub secret: String,
-    pub passphrase: String,
+    pub secret: Secret,
+    pub passphrase: Secret,
+}
+
+impl ApiCreds {
+    pub fn new(api_key: &str, secret: impl Into<Secret>, passphrase: impl Into<Secret>) -> Self {
+        ApiCreds {
+            api_key: api_key.to_owned(),
+            secret: secret.into(),
+            passphrase: passphrase.into(),
+        }
+    }
+
+    /// A view of these credentials that serializes the secret and passphrase in plain
+    /// text, in the same format they are deserialized from. `ApiCreds` itself does not
+    /// implement `Serialize`, so persisting credentials has to go through this.
+    pub fn expose_secrets(&self) -> ExposedApiCreds<'_> {
+        ExposedApiCreds {
+            api_key: &self.api_key,
+            secret: self.secret.expose_secret(),
+            passphrase: self.passphrase.expose_secret(),
+        }
+    }
+}
+
+/// Serializable form of [`ApiCreds`], returned by [`ApiCreds::expose_secrets`].
+#[derive(Serialize)]
+pub struct ExposedApiCreds<'a> {
+    #[serde(rename = "apiKey")]
+    api_key: &'a str,
+    secret: &'a str,
+    passphrase: &'a str,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -373,7 +651,7 @@ pub struct SimplifiedMarketsResponse {
     pub data: Vec<SimplifiedMarket>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Market {
     pub condition_id: String,
     pub tokens: [Token; 2],
@@ -395,6 +673,12 @@ pub struct Market {
     pub seconds_delay: Decimal,
     pub icon: String,
     pub fpmm: String,
+    /// Whether the market is one outcome of a neg-risk event.
+    #[serde(default)]
+    pub neg_risk: bool,
+    /// ID shared by the markets of a neg-risk event.
+    #[serde(default)]
+    pub neg_risk_market_id: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -408,13 +692,13 @@ pub struct SimplifiedMarket {
     pub closed: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Token {
     pub token_id: String,
     pub outcome: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Rewards {
     pub rates: Option<Value>,
     pub min_size: Decimal,
@@ -0,0 +1,241 @@
+//! Cancels resting orders when the strategy stops heartbeating.
+
+use crate::{ClientResult, OrderVenue};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+#[derive(Debug, Clone)]
+pub struct DeadMansSwitchConfig {
+    /// Time without a heartbeat after which all orders are cancelled.
+    pub timeout: Duration,
+    /// How often the heartbeat is checked.
+    pub check_interval: Duration,
+    /// Markets (condition IDs) to also clear with `cancel_market_orders`.
+    pub markets: Vec<String>,
+    /// Attempts per cancel request before giving up.
+    pub max_attempts: u32,
+    pub retry_delay: Duration,
+}
+
+impl Default for DeadMansSwitchConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(30),
+            check_interval: Duration::from_secs(1),
+            markets: Vec::new(),
+            max_attempts: 5,
+            retry_delay: Duration::from_millis(500),
+        }
+    }
+}
+
+/// Cheap handle for signalling liveness from any task.
+#[derive(Clone)]
+pub struct Heartbeat {
+    start: Instant,
+    last: Arc<AtomicU64>,
+}
+
+impl Heartbeat {
+    pub fn beat(&self) {
+        let elapsed = self.start.elapsed().as_millis() as u64;
+        self.last.store(elapsed, Ordering::Relaxed);
+    }
+
+    fn since_last(&self) -> Duration {
+        let last = self.last.load(Ordering::Relaxed);
+        self.start.elapsed() - Duration::from_millis(last)
+    }
+}
+
+/// Background task that calls `cancel_all`, then `cancel_market_orders` for each
+/// configured market, once no heartbeat has been seen for `timeout`. It fires once per
+/// stall and rearms on the next heartbeat. Must be spawned inside a tokio runtime.
+pub struct DeadMansSwitch {
+    heartbeat: Heartbeat,
+    fired: Arc<AtomicBool>,
+    shutdown: oneshot::Sender<()>,
+    task: JoinHandle<ClientResult<()>>,
+}
+
+impl DeadMansSwitch {
+    pub fn spawn<V: OrderVenue + ?Sized + 'static>(
+        venue: Arc<V>,
+        config: DeadMansSwitchConfig,
+    ) -> Self {
+        let heartbeat = Heartbeat {
+            start: Instant::now(),
+            last: Arc::new(AtomicU64::new(0)),
+        };
+        let fired = Arc::new(AtomicBool::new(false));
+        let (shutdown, mut shutdown_rx) = oneshot::channel();
+
+        let task = tokio::spawn({
+            let heartbeat = heartbeat.clone();
+            let fired = fired.clone();
+            async move {
+                let mut interval = tokio::time::interval(config.check_interval);
+                let mut stalled = false;
+                loop {
+                    tokio::select! {
+                        _ = &mut shutdown_rx => break,
+                        _ = interval.tick() => {}
+                    }
+                    let since_last = heartbeat.since_last();
+                    if since_last < config.timeout {
+                        stalled = false;
+                        continue;
+                    }
+                    if !stalled {
+                        stalled = true;
+                        tracing::warn!(
+                            since_last_ms = since_last.as_millis() as u64,
+                            "heartbeat missed, cancelling all orders"
+                        );
+                        fired.store(true, Ordering::Relaxed);
+                        // Keep watching even if cancelling failed; the error has been logged.
+                        let _ = cancel_everything(venue.as_ref(), &config).await;
+                    }
+                }
+                tracing::info!("dead man's switch shutting down, cancelling all orders");
+                cancel_everything(venue.as_ref(), &config).await
+            }
+        });
+
+        Self {
+            heartbeat,
+            fired,
+            shutdown,
+            task,
+        }
+    }
+
+    pub fn heartbeat(&self) {
+        self.heartbeat.beat();
+    }
+
+    /// A handle that can be moved into other tasks to heartbeat.
+    pub fn heartbeat_handle(&self) -> Heartbeat {
+        self.heartbeat.clone()
+    }
+
+    /// Whether the switch has cancelled orders because of a missed heartbeat.
+    pub fn has_fired(&self) -> bool {
+        self.fired.load(Ordering::Relaxed)
+    }
+
+    /// Stops the task after cancelling all resting orders.
+    pub async fn shutdown(self) -> ClientResult<()> {
+        let _ = self.shutdown.send(());
+        self.task.await?
+    }
+}
+
+async fn cancel_everything<V: OrderVenue + ?Sized>(
+    venue: &V,
+    config: &DeadMansSwitchConfig,
+) -> ClientResult<()> {
+    let mut result = with_retries(config, "cancel_all", || venue.cancel_all())
+        .await
+        .map(drop);
+    for market in &config.markets {
+        let cancelled = with_retries(config, "cancel_market_orders", || {
+            venue.cancel_market_orders(Some(market), None)
+        })
+        .await;
+        result = result.and(cancelled.map(drop));
+    }
+    result
+}
+
+async fn with_retries<F, Fut, T>(
+    config: &DeadMansSwitchConfig,
+    name: &str,
+    mut f: F,
+) -> ClientResult<T>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = ClientResult<T>>,
+{
+    let mut attempt = 1;
+    loop {
+        match f().await {
+            Ok(v) => return Ok(v),
+            Err(e) if attempt < config.max_attempts => {
+                tracing::warn!(request = name, attempt, error = %e, "cancel failed, retrying");
+                attempt += 1;
+                tokio::time::sleep(config.retry_delay).await;
+            }
+            Err(e) => {
+                tracing::error!(request = name, attempt, error = %e, "cancel failed, giving up");
+                return Err(e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{OrderBookSummary, OrderSummary, OrderType, PaperExchange, SignedOrderRequest};
+    use rust_decimal::Decimal;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_dead_mans_switch_cancels_after_timeout() {
+        let paper = Arc::new(PaperExchange::new());
+        paper.seed_book(&OrderBookSummary {
+            market: "0xmarket".into(),
+            asset_id: "1".into(),
+            hash: String::new(),
+            timestamp: 0,
+            bids: vec![],
+            asks: vec![OrderSummary {
+                price: Decimal::new(6, 1),
+                size: Decimal::from(10),
+            }],
+        });
+        let order = SignedOrderRequest {
+            salt: 1,
+            maker: "0xmaker".into(),
+            signer: "0xmaker".into(),
+            taker: "0x0000000000000000000000000000000000000000".into(),
+            token_id: "1".into(),
+            maker_amount: "5000000".into(),
+            taker_amount: "10000000".into(),
+            expiration: "0".into(),
+            nonce: "0".into(),
+            fee_rate_bps: "0".into(),
+            side: "BUY".into(),
+            signature_type: 0,
+            signature: "0x".into(),
+            post_only: false,
+        };
+        let resp = paper.post_order(order, OrderType::GTC).await.unwrap();
+        let order_id = resp["orderID"].as_str().unwrap();
+
+        let switch = DeadMansSwitch::spawn(
+            paper.clone(),
+            DeadMansSwitchConfig {
+                timeout: Duration::from_millis(100),
+                check_interval: Duration::from_millis(10),
+                ..Default::default()
+            },
+        );
+        let heartbeat = switch.heartbeat_handle();
+        for _ in 0..5 {
+            tokio::time::sleep(Duration::from_millis(30)).await;
+            heartbeat.beat();
+        }
+        assert!(!switch.has_fired());
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(switch.has_fired());
+        let status = paper.get_order(order_id).await.unwrap().unwrap().status;
+        assert_eq!(status, "CANCELED");
+        switch.shutdown().await.unwrap();
+    }
+}
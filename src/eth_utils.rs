@@ -1,13 +1,15 @@
 use crate::ClientResult;
 use alloy_primitives::U256;
 use alloy_primitives::{hex::encode_prefixed, Address};
-use alloy_signer::{Signer, SignerSync};
-use alloy_sol_types::{eip712_domain, sol};
+use alloy_signer::Signer;
+use alloy_sol_types::{eip712_domain, sol, Eip712Domain};
 use anyhow::Context;
 
-pub trait EthSigner: Signer + SignerSync + Send + Sync {}
+/// Any `alloy_signer::Signer` can sign orders and auth messages, including signers that
+/// live behind a network call such as [`RemoteSigner`](crate::RemoteSigner).
+pub trait EthSigner: Signer + Send + Sync {}
 
-impl<T: Signer + SignerSync + Send + Sync> EthSigner for T {}
+impl<T: Signer + Send + Sync> EthSigner for T {}
 
 sol! {
     struct ClobAuth {
@@ -35,17 +37,17 @@ sol! {
     }
 }
 
-pub fn sign_clob_auth_message(
-    signer: &impl EthSigner,
+pub fn clob_auth_payload(
+    address: Address,
     timestamp: String,
     nonce: U256,
-) -> ClientResult<String> {
+) -> (ClobAuth, Eip712Domain) {
     // TODO: move these constants out
     let message = "This message attests that I control the given wallet".to_owned();
     let polygon = 137;
 
     let my_struct = ClobAuth {
-        address: signer.address(),
+        address,
         timestamp,
         nonce,
         message,
@@ -57,14 +59,25 @@ pub fn sign_clob_auth_message(
         chain_id: polygon,
     );
 
+    (my_struct, my_domain)
+}
+
+pub async fn sign_clob_auth_message(
+    signer: &impl EthSigner,
+    timestamp: String,
+    nonce: U256,
+) -> ClientResult<String> {
+    let (my_struct, my_domain) = clob_auth_payload(signer.address(), timestamp, nonce);
+
     let val = signer
-        .sign_typed_data_sync(&my_struct, &my_domain)
+        .sign_typed_data(&my_struct, &my_domain)
+        .await
         .context("Error creating EIP-712 signature")?;
 
     Ok(encode_prefixed(val.as_bytes()))
 }
 
-pub fn sign_order_message(
+pub async fn sign_order_message(
     signer: &(impl EthSigner + Sized),
     order: Order,
     chain_id: u64,
@@ -79,7 +92,8 @@ pub fn sign_order_message(
     );
 
     let val = signer
-        .sign_typed_data_sync(&order, &domain)
+        .sign_typed_data(&order, &domain)
+        .await
         .context("Error creating EIP-712 signature for order")?;
 
     Ok(encode_prefixed(val.as_bytes()))
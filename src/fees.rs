@@ -0,0 +1,39 @@
+use crate::Side;
+use rust_decimal::Decimal;
+
+/// Fee in USDC for a fill of `size` shares at `price` by an order signed with `fee_rate_bps`.
+///
+/// Polymarket fees are symmetric around 0.5: `rate * min(price, 1 - price) * size`.
+pub fn calculate_fee(fee_rate_bps: u32, price: Decimal, size: Decimal) -> Decimal {
+    let rate = Decimal::from(fee_rate_bps) / Decimal::from(10_000);
+    rate * price.min(Decimal::ONE - price) * size
+}
+
+/// Price per share once the fee is included: what a buyer pays or a seller receives.
+pub fn effective_price(fee_rate_bps: u32, side: Side, price: Decimal) -> Decimal {
+    let fee_per_share = calculate_fee(fee_rate_bps, price, Decimal::ONE);
+    match side {
+        Side::BUY => price + fee_per_share,
+        Side::SELL => price - fee_per_share,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fees() {
+        let price = Decimal::new(8, 1);
+        assert_eq!(
+            calculate_fee(1000, price, Decimal::from(10)),
+            Decimal::new(2, 1)
+        );
+        assert_eq!(effective_price(1000, Side::BUY, price), Decimal::new(82, 2));
+        assert_eq!(
+            effective_price(1000, Side::SELL, price),
+            Decimal::new(78, 2)
+        );
+        assert_eq!(effective_price(0, Side::SELL, price), price);
+    }
+}
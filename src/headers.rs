@@ -7,20 +7,24 @@ use anyhow::Result;
 use serde::Serialize;
 use std::collections::HashMap;
 
-const POLY_ADDR_HEADER: &str = "poly_address";
-const POLY_SIG_HEADER: &str = "poly_signature";
-const POLY_TS_HEADER: &str = "poly_timestamp";
-const POLY_NONCE_HEADER: &str = "poly_nonce";
-const POLY_API_KEY_HEADER: &str = "poly_api_key";
-const POLY_PASS_HEADER: &str = "poly_passphrase";
+pub const POLY_ADDR_HEADER: &str = "poly_address";
+pub const POLY_SIG_HEADER: &str = "poly_signature";
+pub const POLY_TS_HEADER: &str = "poly_timestamp";
+pub const POLY_NONCE_HEADER: &str = "poly_nonce";
+pub const POLY_API_KEY_HEADER: &str = "poly_api_key";
+pub const POLY_PASS_HEADER: &str = "poly_passphrase";
+
+/// Headers whose values must be masked in logs and recordings.
+pub const SENSITIVE_HEADERS: [&str; 3] = [POLY_SIG_HEADER, POLY_API_KEY_HEADER, POLY_PASS_HEADER];
 
 //TODO: Heapless for maps!
 type Headers = HashMap<&'static str, String>;
 
-pub fn create_l1_headers(signer: &impl EthSigner, nonce: Option<U256>) -> Result<Headers> {
+pub async fn create_l1_headers(signer: &impl EthSigner, nonce: Option<U256>) -> Result<Headers> {
     let timestamp = get_current_unix_time_secs().to_string();
     let nonce = nonce.unwrap_or(U256::ZERO);
-    let signature = sign_clob_auth_message(signer, timestamp.clone(), nonce)?;
+    let signature = sign_clob_auth_message(signer, timestamp.clone(), nonce).await?;
+    tracing::debug!(timestamp = %timestamp, "signed L1 auth message");
     let address = encode_prefixed(signer.address().as_slice());
 
     Ok(HashMap::from([
@@ -44,14 +48,22 @@ where
     let address = encode_prefixed(signer.address().as_slice());
     let timestamp = get_current_unix_time_secs();
 
-    let hmac_signature =
-        build_hmac_signature(&api_creds.secret, timestamp, method, req_path, body)?;
+    let hmac_signature = build_hmac_signature(
+        api_creds.secret.expose_secret(),
+        timestamp,
+        method,
+        req_path,
+        body,
+    )?;
 
     Ok(HashMap::from([
         (POLY_ADDR_HEADER, address),
         (POLY_SIG_HEADER, hmac_signature),
         (POLY_TS_HEADER, timestamp.to_string()),
         (POLY_API_KEY_HEADER, api_creds.api_key.clone()),
-        (POLY_PASS_HEADER, api_creds.passphrase.clone()),
+        (
+            POLY_PASS_HEADER,
+            api_creds.passphrase.expose_secret().to_owned(),
+        ),
     ]))
 }
@@ -1,32 +1,84 @@
 use alloy_primitives::hex::encode_prefixed;
 pub use alloy_primitives::U256;
-use alloy_signer_local::PrivateKeySigner;
+pub use alloy_signer_local::PrivateKeySigner;
 pub use anyhow::{anyhow, Context, Result as ClientResult};
 use config::get_contract_config;
-use orders::OrderBuilder;
-use orders::SignedOrderRequest;
-use reqwest::header::HeaderName;
+use orders::{quote_market_buy, OrderBuilder};
+use reqwest::header::{HeaderName, HeaderValue};
 use reqwest::Client;
 use reqwest::Method;
 use reqwest::RequestBuilder;
+use reqwest::Response;
 use rust_decimal::Decimal;
 pub use serde_json::Value;
 use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::Instrument;
 
 // #[cfg(test)]
 // mod tests;
 
+mod arb;
+mod backtest;
+mod book;
+mod cassette;
 mod config;
 mod data;
+mod dead_mans_switch;
 mod eth_utils;
+mod fees;
 mod headers;
+mod market_data;
+mod metadata;
+mod middleware;
+#[cfg(feature = "mock")]
+pub mod mock;
+mod order_manager;
 mod orders;
+mod paper;
+mod portfolio;
+mod remote_signer;
+mod risk;
+mod secret;
 mod utils;
-
+mod validation;
+mod venue;
+#[cfg(any(feature = "keystore", feature = "mnemonic"))]
+mod wallet;
+
+pub use arb::{ArbKind, ArbLeg, ArbOpportunity, ArbScanner, ArbScannerConfig};
+pub use backtest::{
+    Backtest, BacktestConfig, BacktestContext, BacktestFill, BacktestReport, InventoryPoint,
+    Liquidity, MarketEvent, SimOrder, Strategy,
+};
+pub use book::{ImpactPoint, SortedBook, SyntheticBook};
+pub use cassette::{Interaction, Recorder, Replayer};
 pub use data::*;
+pub use dead_mans_switch::{DeadMansSwitch, DeadMansSwitchConfig, Heartbeat};
 pub use eth_utils::EthSigner;
-pub use orders::SigType;
-use headers::{create_l1_headers, create_l2_headers};
+pub use fees::{calculate_fee, effective_price};
+use headers::{create_l1_headers, create_l2_headers, SENSITIVE_HEADERS};
+pub use market_data::{
+    MarketDataReader, MarketDataWriter, MarketRecorder, RecordedEvent, RecorderConfig,
+};
+use metadata::MetadataCache;
+pub use metadata::MetadataCacheConfig;
+pub use middleware::{Middleware, Next};
+pub use order_manager::{ManagedOrder, OrderManager, OrderState};
+pub use orders::{round_price_to_tick, SigType, SignedOrderRequest};
+pub use paper::{PaperExchange, PAPER_OWNER};
+pub use portfolio::{Portfolio, Position, PositionMismatch};
+pub use remote_signer::RemoteSigner;
+pub use risk::{RiskGuard, RiskLimits, RiskViolation};
+pub use secret::Secret;
+use validation::{
+    check_post_only, check_post_only_resting, check_slippage, gtd_expiration, OrderValidation,
+};
+pub use validation::{OrderValidationError, GTD_EXPIRATION_BUFFER_SECS};
+pub use venue::OrderVenue;
+#[cfg(any(feature = "keystore", feature = "mnemonic"))]
+pub use wallet::*;
 
 #[derive(Default)]
 pub struct ClobClient {
@@ -36,10 +88,21 @@ pub struct ClobClient {
     chain_id: Option<u64>,
     api_creds: Option<ApiCreds>,
     order_builder: Option<OrderBuilder>,
+    middlewares: Vec<Arc<dyn Middleware>>,
+    risk_guard: Option<Arc<RiskGuard>>,
+    metadata: MetadataCache,
+}
+
+fn parse_private_key(key: &str) -> PrivateKeySigner {
+    key.parse::<PrivateKeySigner>()
+        .expect("Invalid private key")
 }
 
 const INITIAL_CURSOR: &str = "MA==";
 const END_CURSOR: &str = "LTE=";
+/// Text of the exchange's rejection for a price off the tick grid. Seeing it means the
+/// cached tick size is stale.
+const TICK_SIZE_REJECTION: &str = "minimum tick size";
 
 impl ClobClient {
     // TODO: initial headers, gzip
@@ -51,18 +114,7 @@ impl ClobClient {
         }
     }
     pub fn with_l1_headers(host: &str, key: &str, chain_id: u64) -> Self {
-        let signer = Box::new(
-            key.parse::<PrivateKeySigner>()
-                .expect("Invalid private key"),
-        );
-        Self {
-            host: host.to_owned(),
-            http_client: Client::new(),
-            signer: Some(signer.clone()),
-            chain_id: Some(chain_id),
-            api_creds: None,
-            order_builder: Some(OrderBuilder::new(signer, None, None)),
-        }
+        Self::with_signer(host, parse_private_key(key), chain_id)
     }
 
     pub fn with_l1_headers_proxy(
@@ -72,34 +124,19 @@ impl ClobClient {
         funder: Option<&str>,
         signature_type: Option<SigType>,
     ) -> Self {
-        let signer = Box::new(
-            key.parse::<PrivateKeySigner>()
-                .expect("Invalid private key"),
-        );
-        let funder_address = funder.map(|f| f.parse().expect("Invalid funder address"));
-        Self {
-            host: host.to_owned(),
-            http_client: Client::new(),
-            signer: Some(signer.clone()),
-            chain_id: Some(chain_id),
-            api_creds: None,
-            order_builder: Some(OrderBuilder::new(signer, signature_type, funder_address)),
-        }
+        Self::with_signer_proxy(
+            host,
+            parse_private_key(key),
+            chain_id,
+            funder,
+            signature_type,
+        )
     }
 
     pub fn with_l2_headers(host: &str, key: &str, chain_id: u64, api_creds: ApiCreds) -> Self {
-        let signer = Box::new(
-            key.parse::<PrivateKeySigner>()
-                .expect("Invalid private key"),
-        );
-        Self {
-            host: host.to_owned(),
-            http_client: Client::new(),
-            signer: Some(signer.clone()),
-            chain_id: Some(chain_id),
-            api_creds: Some(api_creds),
-            order_builder: Some(OrderBuilder::new(signer, None, None)),
-        }
+        let mut client = Self::with_l1_headers(host, key, chain_id);
+        client.set_api_creds(api_creds);
+        client
     }
 
     pub fn with_l2_headers_proxy(
@@ -110,24 +147,104 @@ impl ClobClient {
         funder: Option<&str>,
         signature_type: Option<SigType>,
     ) -> Self {
-        let signer = Box::new(
-            key.parse::<PrivateKeySigner>()
-                .expect("Invalid private key"),
-        );
+        let mut client = Self::with_l1_headers_proxy(host, key, chain_id, funder, signature_type);
+        client.set_api_creds(api_creds);
+        client
+    }
+
+    /// Creates a client backed by any [`EthSigner`], e.g. a [`RemoteSigner`] or a KMS signer.
+    pub fn with_signer<S: EthSigner + Clone + 'static>(
+        host: &str,
+        signer: S,
+        chain_id: u64,
+    ) -> Self {
+        Self::with_signer_proxy(host, signer, chain_id, None, None)
+    }
+
+    pub fn with_signer_proxy<S: EthSigner + Clone + 'static>(
+        host: &str,
+        signer: S,
+        chain_id: u64,
+        funder: Option<&str>,
+        signature_type: Option<SigType>,
+    ) -> Self {
         let funder_address = funder.map(|f| f.parse().expect("Invalid funder address"));
         Self {
             host: host.to_owned(),
             http_client: Client::new(),
-            signer: Some(signer.clone()),
+            signer: Some(Box::new(signer.clone())),
             chain_id: Some(chain_id),
-            api_creds: Some(api_creds),
-            order_builder: Some(OrderBuilder::new(signer, signature_type, funder_address)),
+            api_creds: None,
+            order_builder: Some(OrderBuilder::new(
+                Box::new(signer),
+                signature_type,
+                funder_address,
+            )),
+            middlewares: Vec::new(),
+            risk_guard: None,
+            metadata: MetadataCache::default(),
         }
     }
+
     pub fn set_api_creds(&mut self, api_creds: ApiCreds) {
         self.api_creds = Some(api_creds);
     }
 
+    /// Appends a [`Middleware`] that runs around every request sent by this client.
+    pub fn add_middleware(&mut self, middleware: impl Middleware + 'static) {
+        self.middlewares.push(Arc::new(middleware));
+    }
+
+    /// Sets how long tick sizes, neg-risk flags and fee rates are cached per token.
+    pub fn set_metadata_cache_config(&mut self, config: MetadataCacheConfig) {
+        self.metadata.set_config(config);
+    }
+
+    /// Fetches and caches the tick size, neg-risk flag and fee rate of each token so the
+    /// first `create_order` for it does not have to.
+    pub async fn prewarm_metadata(&self, token_ids: &[String]) -> ClientResult<()> {
+        for token_id in token_ids {
+            self.get_tick_size(token_id).await?;
+            self.get_neg_risk(token_id).await?;
+            self.get_fee_rate_bps(token_id).await?;
+        }
+        Ok(())
+    }
+
+    /// Drops cached metadata for `token_id`.
+    pub fn invalidate_metadata(&self, token_id: &str) {
+        self.metadata.invalidate(token_id);
+    }
+
+    pub fn clear_metadata_cache(&self) {
+        self.metadata.clear();
+    }
+
+    /// Records the best bid and ask of `token_id`, e.g. from a market channel feed, for the
+    /// post-only check. Books fetched with `get_order_book(s)` are recorded automatically.
+    pub fn update_top_of_book(
+        &self,
+        token_id: &str,
+        best_bid: Option<Decimal>,
+        best_ask: Option<Decimal>,
+    ) {
+        self.metadata
+            .set_top_of_book(token_id, (best_bid, best_ask));
+    }
+
+    /// Applies a `tick_size_change` message to the cache.
+    pub fn on_tick_size_change(&self, event: &TickSizeChange) {
+        self.metadata
+            .set_tick_size(&event.asset_id, event.new_tick_size);
+    }
+
+    /// Checks every order against `guard` in `create_order` and `create_market_order`,
+    /// before it is signed. Orders posted and cancelled through this client update the
+    /// guard's open-order exposure.
+    pub fn set_risk_guard(&mut self, guard: Arc<RiskGuard>) {
+        self.risk_guard = Some(guard);
+    }
+
     #[inline]
     fn get_l1_parameters(&self) -> (&impl EthSigner, u64) {
         let signer = self.signer.as_ref().expect("Signer is not set");
@@ -170,59 +287,111 @@ impl ClobClient {
             .http_client
             .request(method, format!("{}{endpoint}", &self.host));
 
-        headers.fold(req, |r, (k, v)| r.header(HeaderName::from_static(k), v))
+        headers.fold(req, |r, (k, v)| {
+            let mut value = HeaderValue::from_str(&v).expect("Invalid header value");
+            value.set_sensitive(SENSITIVE_HEADERS.contains(&k));
+            r.header(HeaderName::from_static(k), value)
+        })
+    }
+
+    async fn send(&self, req: RequestBuilder) -> ClientResult<Response> {
+        self.send_attempt(req, 0).await
+    }
+
+    /// Sends `req`, recording on its span how many earlier attempts preceded it.
+    async fn send_attempt(&self, req: RequestBuilder, retries: u32) -> ClientResult<Response> {
+        let req = req.build()?;
+        let span = tracing::info_span!(
+            "clob_request",
+            method = %req.method(),
+            endpoint = req.url().path(),
+            retries,
+            status = tracing::field::Empty,
+            latency_ms = tracing::field::Empty,
+        );
+
+        let start = Instant::now();
+        let resp = Next::new(&self.http_client, &self.middlewares)
+            .run(req)
+            .instrument(span.clone())
+            .await;
+        span.record("latency_ms", start.elapsed().as_millis() as u64);
+
+        match resp {
+            Ok(resp) => {
+                span.record("status", resp.status().as_u16());
+                Ok(resp)
+            }
+            Err(e) => {
+                span.in_scope(|| tracing::warn!(error = %e, "request failed"));
+                Err(e)
+            }
+        }
     }
 
+    #[tracing::instrument(skip_all)]
     pub async fn get_ok(&self) -> bool {
-        self.http_client
-            .get(format!("{}/", &self.host))
-            .send()
+        self.send(self.http_client.get(format!("{}/", &self.host)))
             .await
             .is_ok()
     }
 
+    #[tracing::instrument(skip_all)]
     pub async fn get_server_time(&self) -> ClientResult<u64> {
-        let resp = self
-            .http_client
-            .get(format!("{}/time", &self.host))
-            .send()
-            .await?
-            .text()
-            .await?
-            .parse::<u64>()?;
+        let req = self.http_client.get(format!("{}/time", &self.host));
+
+        let resp = self.send(req).await?.text().await?.parse::<u64>()?;
         Ok(resp)
     }
 
+    #[tracing::instrument(skip_all)]
     pub async fn create_api_key(&self, nonce: Option<U256>) -> ClientResult<ApiCreds> {
         let method = Method::POST;
         let endpoint = "/auth/api-key";
         let (signer, _) = self.get_l1_parameters();
-        let headers = create_l1_headers(signer, nonce)?;
+        let headers = create_l1_headers(signer, nonce).await?;
 
         let req = self.create_request_with_headers(method, endpoint, headers.into_iter());
 
-        Ok(req.send().await?.json::<ApiCreds>().await?)
+        Ok(self.send(req).await?.json::<ApiCreds>().await?)
     }
 
+    #[tracing::instrument(skip_all)]
     pub async fn derive_api_key(&self, nonce: Option<U256>) -> ClientResult<ApiCreds> {
+        self.derive_api_key_attempt(nonce, 0).await
+    }
+
+    async fn derive_api_key_attempt(
+        &self,
+        nonce: Option<U256>,
+        retries: u32,
+    ) -> ClientResult<ApiCreds> {
         let method = Method::GET;
         let endpoint = "/auth/derive-api-key";
         let (signer, _) = self.get_l1_parameters();
-        let headers = create_l1_headers(signer, nonce)?;
+        let headers = create_l1_headers(signer, nonce).await?;
 
         let req = self.create_request_with_headers(method, endpoint, headers.into_iter());
 
-        Ok(req.send().await?.json::<ApiCreds>().await?)
+        Ok(self
+            .send_attempt(req, retries)
+            .await?
+            .json::<ApiCreds>()
+            .await?)
     }
 
+    #[tracing::instrument(skip_all, fields(retries = 0))]
     pub async fn create_or_derive_api_key(&self, nonce: Option<U256>) -> ClientResult<ApiCreds> {
         let creds = self.create_api_key(nonce).await;
         if creds.is_err() {
-            return self.derive_api_key(nonce).await;
+            tracing::Span::current().record("retries", 1);
+            tracing::debug!("create_api_key failed, deriving existing key");
+            return self.derive_api_key_attempt(nonce, 1).await;
         }
         creds
     }
 
+    #[tracing::instrument(skip_all)]
     pub async fn get_api_keys(&self) -> ClientResult<Vec<String>> {
         let method = Method::GET;
         let endpoint = "/auth/api-keys";
@@ -231,9 +400,15 @@ impl ClobClient {
 
         let req = self.create_request_with_headers(method, endpoint, headers.into_iter());
 
-        Ok(req.send().await?.json::<ApiKeysResponse>().await?.api_keys)
+        Ok(self
+            .send(req)
+            .await?
+            .json::<ApiKeysResponse>()
+            .await?
+            .api_keys)
     }
 
+    #[tracing::instrument(skip_all)]
     pub async fn delete_api_key(&self) -> ClientResult<String> {
         let method = Method::DELETE;
         let endpoint = "/auth/api-key";
@@ -241,20 +416,20 @@ impl ClobClient {
         let headers = create_l2_headers::<Value>(signer, creds, method.as_str(), endpoint, None)?;
         let req = self.create_request_with_headers(method, endpoint, headers.into_iter());
 
-        Ok(req.send().await?.text().await?)
+        Ok(self.send(req).await?.text().await?)
     }
 
+    #[tracing::instrument(skip(self))]
     pub async fn get_midpoint(&self, token_id: &str) -> ClientResult<MidpointResponse> {
-        Ok(self
+        let req = self
             .http_client
             .get(format!("{}/midpoint", &self.host))
-            .query(&[("token_id", token_id)])
-            .send()
-            .await?
-            .json::<MidpointResponse>()
-            .await?)
+            .query(&[("token_id", token_id)]);
+
+        Ok(self.send(req).await?.json::<MidpointResponse>().await?)
     }
 
+    #[tracing::instrument(skip_all, fields(tokens = token_ids.len()))]
     pub async fn get_midpoints(
         &self,
         token_ids: &[String],
@@ -264,27 +439,29 @@ impl ClobClient {
             .map(|b| HashMap::from([("token_id", b.clone())]))
             .collect::<Vec<HashMap<&str, String>>>();
 
-        Ok(self
+        let req = self
             .http_client
             .post(format!("{}/midpoints", &self.host))
-            .json(&v)
-            .send()
+            .json(&v);
+
+        Ok(self
+            .send(req)
             .await?
             .json::<HashMap<String, Decimal>>()
             .await?)
     }
 
+    #[tracing::instrument(skip(self))]
     pub async fn get_price(&self, token_id: &str, side: Side) -> ClientResult<PriceResponse> {
-        Ok(self
+        let req = self
             .http_client
             .get(format!("{}/price", &self.host))
             .query(&[("token_id", token_id)])
-            .query(&[("side", side.as_str())])
-            .send()
-            .await?
-            .json::<PriceResponse>()
-            .await?)
+            .query(&[("side", side.as_str())]);
+
+        Ok(self.send(req).await?.json::<PriceResponse>().await?)
     }
+    #[tracing::instrument(skip_all, fields(tokens = book_params.len()))]
     pub async fn get_prices(
         &self,
         book_params: &[BookParams],
@@ -299,27 +476,29 @@ impl ClobClient {
             })
             .collect::<Vec<HashMap<&str, String>>>();
 
-        Ok(self
+        let req = self
             .http_client
             .post(format!("{}/prices", &self.host))
-            .json(&v)
-            .send()
+            .json(&v);
+
+        Ok(self
+            .send(req)
             .await?
             .json::<HashMap<String, HashMap<Side, Decimal>>>()
             .await?)
     }
 
+    #[tracing::instrument(skip(self))]
     pub async fn get_spread(&self, token_id: &str) -> ClientResult<SpreadResponse> {
-        Ok(self
+        let req = self
             .http_client
             .get(format!("{}/spread", &self.host))
-            .query(&[("token_id", token_id)])
-            .send()
-            .await?
-            .json::<SpreadResponse>()
-            .await?)
+            .query(&[("token_id", token_id)]);
+
+        Ok(self.send(req).await?.json::<SpreadResponse>().await?)
     }
 
+    #[tracing::instrument(skip_all, fields(tokens = token_ids.len()))]
     pub async fn get_spreads(
         &self,
         token_ids: &[String],
@@ -329,40 +508,60 @@ impl ClobClient {
             .map(|b| HashMap::from([("token_id", b.clone())]))
             .collect::<Vec<HashMap<&str, String>>>();
 
-        Ok(self
+        let req = self
             .http_client
             .post(format!("{}/spreads", &self.host))
-            .json(&v)
-            .send()
+            .json(&v);
+
+        Ok(self
+            .send(req)
             .await?
             .json::<HashMap<String, Decimal>>()
             .await?)
     }
 
-    // cache
+    /// Minimum tick size of `token_id`.
+    ///
+    /// After the first lookup the value is served from the client's cache for
+    /// [`MetadataCacheConfig::tick_size_ttl`], so a tick size change is only seen once
+    /// the entry expires or is invalidated.
+    #[tracing::instrument(skip(self))]
     pub async fn get_tick_size(&self, token_id: &str) -> ClientResult<Decimal> {
-        Ok(self
+        if let Some(tick_size) = self.metadata.tick_size(token_id) {
+            return Ok(tick_size);
+        }
+        let req = self
             .http_client
             .get(format!("{}/tick-size", &self.host))
-            .query(&[("token_id", token_id)])
-            .send()
+            .query(&[("token_id", token_id)]);
+
+        let tick_size = self
+            .send(req)
             .await?
             .json::<TickSizeResponse>()
             .await?
-            .minimum_tick_size)
+            .minimum_tick_size;
+        self.metadata.set_tick_size(token_id, tick_size);
+        Ok(tick_size)
     }
+
     /// Query the fee rate (in basis points) for a given token ID.
     /// Fee-enabled markets return a non-zero value; fee-free markets return 0.
     /// The API returns {"base_fee": 1000} for sports markets.
+    ///
+    /// After the first lookup the value is served from the client's cache for
+    /// [`MetadataCacheConfig::fee_rate_ttl`].
+    #[tracing::instrument(skip(self))]
     pub async fn get_fee_rate_bps(&self, token_id: &str) -> ClientResult<u32> {
-        let response = self
+        if let Some(fee_rate) = self.metadata.fee_rate_bps(token_id) {
+            return Ok(fee_rate);
+        }
+        let req = self
             .http_client
             .get(format!("{}/fee-rate", &self.host))
-            .query(&[("token_id", token_id)])
-            .send()
-            .await?
-            .json::<Value>()
-            .await?;
+            .query(&[("token_id", token_id)]);
+
+        let response = self.send(req).await?.json::<Value>().await?;
 
         // The API returns {"base_fee": 1000} for fee-enabled markets
         let fee_rate = response
@@ -376,20 +575,32 @@ impl ClobClient {
             })
             .context(format!("No fee rate found in API response: {}", response))?;
 
+        self.metadata.set_fee_rate_bps(token_id, fee_rate);
         Ok(fee_rate)
     }
 
-    // Cache
+    /// Whether `token_id` belongs to a neg-risk market.
+    ///
+    /// After the first lookup the value is served from the client's cache for
+    /// [`MetadataCacheConfig::neg_risk_ttl`].
+    #[tracing::instrument(skip(self))]
     pub async fn get_neg_risk(&self, token_id: &str) -> ClientResult<bool> {
-        Ok(self
+        if let Some(neg_risk) = self.metadata.neg_risk(token_id) {
+            return Ok(neg_risk);
+        }
+        let req = self
             .http_client
             .get(format!("{}/neg-risk", &self.host))
-            .query(&[("token_id", token_id)])
-            .send()
+            .query(&[("token_id", token_id)]);
+
+        let neg_risk = self
+            .send(req)
             .await?
             .json::<NegRiskResponse>()
             .await?
-            .neg_risk)
+            .neg_risk;
+        self.metadata.set_neg_risk(token_id, neg_risk);
+        Ok(neg_risk)
     }
 
     async fn resolve_tick_size(
@@ -414,6 +625,43 @@ impl ClobClient {
         }
     }
 
+    async fn resolve_fee_rate(
+        &self,
+        token_id: &str,
+        fee_rate_bps: Option<u32>,
+    ) -> ClientResult<u32> {
+        match fee_rate_bps {
+            Some(f) => Ok(f),
+            None => self
+                .get_fee_rate_bps(token_id)
+                .await
+                .context("Error fetching fee rate"),
+        }
+    }
+
+    /// Fee in USDC for a fill of `size` shares of `token_id` at `price`, at the token's
+    /// current fee rate.
+    pub async fn expected_fee(
+        &self,
+        token_id: &str,
+        price: Decimal,
+        size: Decimal,
+    ) -> ClientResult<Decimal> {
+        let fee_rate_bps = self.get_fee_rate_bps(token_id).await?;
+        Ok(calculate_fee(fee_rate_bps, price, size))
+    }
+
+    /// Price per share of a fill of `token_id` at `price` once fees are included.
+    pub async fn effective_price(
+        &self,
+        token_id: &str,
+        side: Side,
+        price: Decimal,
+    ) -> ClientResult<Decimal> {
+        let fee_rate_bps = self.get_fee_rate_bps(token_id).await?;
+        Ok(effective_price(fee_rate_bps, side, price))
+    }
+
     async fn get_filled_order_options(
         &self,
         token_id: &str,
@@ -437,16 +685,80 @@ impl ClobClient {
         })
     }
 
-    fn is_price_in_range(&self, price: Decimal, tick_size: Decimal) -> bool {
-        let min_price = tick_size;
-        let max_price = Decimal::ONE - tick_size;
+    /// The market `token_id` belongs to. Both the lookup and the market are cached.
+    #[tracing::instrument(skip(self))]
+    pub async fn get_market_for_token(&self, token_id: &str) -> ClientResult<Market> {
+        let condition_id = match self.metadata.condition_id(token_id) {
+            Some(condition_id) => condition_id,
+            None => {
+                let condition_id = self.get_order_book(token_id).await?.market;
+                self.metadata.set_condition_id(token_id, &condition_id);
+                condition_id
+            }
+        };
+        if let Some(market) = self.metadata.market(&condition_id) {
+            return Ok(market);
+        }
+        let market = self.get_market(&condition_id).await?;
+        self.metadata.set_market(market.clone());
+        Ok(market)
+    }
+
+    /// `now` is the exchange's time in unix seconds, which a nonzero `expiration` is
+    /// checked against.
+    #[allow(clippy::too_many_arguments)]
+    async fn validate_order(
+        &self,
+        token_id: &str,
+        price: Decimal,
+        size: Decimal,
+        tick_size: Decimal,
+        expiration: u64,
+        now: u64,
+        fee_rate_bps: u32,
+    ) -> ClientResult<()> {
+        let market = self.get_market_for_token(token_id).await?;
+        let market_fee_rate_bps = self.get_fee_rate_bps(token_id).await?;
+        OrderValidation {
+            price,
+            size,
+            tick_size,
+            expiration,
+            fee_rate_bps,
+            market_fee_rate_bps,
+            market: &market,
+            now,
+        }
+        .validate()
+        .inspect_err(|error| tracing::warn!(%error, "order failed validation"))?;
+        Ok(())
+    }
 
-        if price < min_price || price > max_price {
-            return false;
+    async fn check_risk(
+        &self,
+        token_id: &str,
+        side: Side,
+        price: Decimal,
+        size: Decimal,
+    ) -> ClientResult<()> {
+        let Some(guard) = &self.risk_guard else {
+            return Ok(());
+        };
+        let midpoint = match guard.limits().price_collar {
+            Some(_) => Some(self.get_midpoint(token_id).await?.mid),
+            None => None,
+        };
+        if guard.limits().max_position_per_market.is_some() && guard.market_of(token_id).is_none() {
+            let book = self.get_order_book(token_id).await?;
+            guard.set_market(token_id, &book.market);
         }
-        true
+        guard
+            .check(token_id, side, price, size, midpoint)
+            .inspect_err(|violation| tracing::warn!(%violation, "order rejected by risk guard"))?;
+        Ok(())
     }
 
+    #[tracing::instrument(skip_all, fields(token_id = %order_args.token_id, side = order_args.side.as_str(), price = %order_args.price, size = %order_args.size))]
     pub async fn create_order(
         &self,
         order_args: &OrderArgs,
@@ -455,21 +767,61 @@ impl ClobClient {
         options: Option<&CreateOrderOptions>,
     ) -> ClientResult<SignedOrderRequest> {
         let (_, chain_id) = self.get_l1_parameters();
+        if order_args.post_only {
+            check_post_only_resting(order_args.time_in_force)
+                .inspect_err(|error| tracing::warn!(%error, "post-only order refused"))?;
+        }
 
         let create_order_options = self
             .get_filled_order_options(order_args.token_id.as_ref(), options)
             .await?;
-        let expiration = expiration.unwrap_or(0);
-        let extras = extras.unwrap_or_default();
+        // The exchange expires orders by its own clock, which ours may drift from, so
+        // expirations are computed and checked against the same server time.
+        let (expiration, now) = match (expiration, order_args.time_in_force) {
+            (Some(0), _) | (None, TimeInForce::GTC | TimeInForce::FOK | TimeInForce::FAK) => (0, 0),
+            (Some(expiration), _) => (expiration, self.get_server_time().await?),
+            (None, TimeInForce::GTD(expiry)) => {
+                let now = self.get_server_time().await?;
+                (gtd_expiration(expiry, now), now)
+            }
+        };
+        let mut extras = extras.unwrap_or_default();
+        let fee_rate_bps = self
+            .resolve_fee_rate(&order_args.token_id, extras.fee_rate_bps)
+            .await?;
+        extras.fee_rate_bps = Some(fee_rate_bps);
 
-        if !self.is_price_in_range(
+        self.validate_order(
+            &order_args.token_id,
             order_args.price,
+            order_args.size,
             create_order_options.tick_size.expect("Should be filled"),
-        ) {
-            return Err(anyhow!("Price is not in range of tick_size"));
+            expiration,
+            now,
+            fee_rate_bps,
+        )
+        .await?;
+        if order_args.post_only {
+            let (best_bid, best_ask) = match self.metadata.top_of_book(&order_args.token_id) {
+                Some(top) => top,
+                None => {
+                    let book = self.get_order_book(&order_args.token_id).await?;
+                    (book.best_bid(), book.best_ask())
+                }
+            };
+            check_post_only(order_args.side, order_args.price, best_bid, best_ask)
+                .inspect_err(|error| tracing::warn!(%error, "post-only order refused"))?;
         }
+        self.check_risk(
+            &order_args.token_id,
+            order_args.side,
+            order_args.price,
+            order_args.size,
+        )
+        .await?;
 
-        self.order_builder
+        let mut order = self
+            .order_builder
             .as_ref()
             .expect("OrderBuilder not set")
             .create_order(
@@ -479,19 +831,25 @@ impl ClobClient {
                 &extras,
                 create_order_options,
             )
+            .await?;
+        order.post_only = order_args.post_only;
+        Ok(order)
     }
 
+    #[tracing::instrument(skip(self))]
     pub async fn get_order_book(&self, token_id: &str) -> ClientResult<OrderBookSummary> {
-        Ok(self
+        let req = self
             .http_client
             .get(format!("{}/book", &self.host))
-            .query(&[("token_id", token_id)])
-            .send()
-            .await?
-            .json::<OrderBookSummary>()
-            .await?)
+            .query(&[("token_id", token_id)]);
+
+        let book = self.send(req).await?.json::<OrderBookSummary>().await?;
+        self.metadata
+            .set_top_of_book(token_id, (book.best_bid(), book.best_ask()));
+        Ok(book)
     }
 
+    #[tracing::instrument(skip_all, fields(tokens = token_ids.len()))]
     pub async fn get_order_books(
         &self,
         token_ids: &[String],
@@ -501,31 +859,56 @@ impl ClobClient {
             .map(|b| HashMap::from([("token_id", b.clone())]))
             .collect::<Vec<HashMap<&str, String>>>();
 
-        Ok(self
+        let req = self
             .http_client
             .post(format!("{}/books", &self.host))
-            .json(&v)
-            .send()
+            .json(&v);
+
+        let books = self
+            .send(req)
             .await?
             .json::<Vec<OrderBookSummary>>()
-            .await?)
+            .await?;
+        for book in &books {
+            self.metadata
+                .set_top_of_book(&book.asset_id, (book.best_bid(), book.best_ask()));
+        }
+        Ok(books)
     }
 
-    async fn calculate_market_price(
+    /// Fetches the books of both outcomes of a binary market and prices each outcome with
+    /// the liquidity of both, returned in the order of `tokens`.
+    pub async fn get_synthetic_books(
         &self,
-        token_id: &str,
-        side: Side,
-        amount: Decimal,
-    ) -> ClientResult<Decimal> {
-        let book = self.get_order_book(token_id).await?;
-        let ob = self
-            .order_builder
-            .as_ref()
-            .expect("No orderBuilder set for client!");
-        match side {
-            Side::BUY => ob.calculate_market_price(&book.asks, amount),
-            Side::SELL => ob.calculate_market_price(&book.bids, amount),
-        }
+        tokens: &[Token; 2],
+    ) -> ClientResult<[SyntheticBook; 2]> {
+        let token_ids = tokens.clone().map(|t| t.token_id);
+        let books = self.get_order_books(&token_ids).await?;
+        let [first, second] = token_ids.map(|token_id| {
+            books
+                .iter()
+                .find(|b| b.asset_id == token_id)
+                .map(OrderBookSummary::sorted)
+                .with_context(|| format!("No book returned for token {token_id}"))
+        });
+        let (first, second) = (first?, second?);
+        Ok([
+            SyntheticBook::new(&tokens[0], &first, &second),
+            SyntheticBook::new(&tokens[1], &second, &first),
+        ])
+    }
+
+    /// Expected fills of a market buy against the current book. Fails with
+    /// [`OrderValidationError`] when the book is too thin or the fills break the slippage
+    /// limits of `order_args`.
+    pub async fn quote_market_order(
+        &self,
+        order_args: &MarketOrderArgs,
+    ) -> ClientResult<MarketOrderQuote> {
+        let book = self.get_order_book(&order_args.token_id).await?;
+        let quote = quote_market_buy(&book.asks, order_args.amount)?;
+        check_slippage(&quote, order_args.max_slippage, order_args.worst_price)?;
+        Ok(quote)
     }
 
     pub async fn create_market_order(
@@ -534,35 +917,65 @@ impl ClobClient {
         extras: Option<ExtraOrderArgs>,
         options: Option<&CreateOrderOptions>,
     ) -> ClientResult<SignedOrderRequest> {
+        let (order, _) = self
+            .create_market_order_with_quote(order_args, extras, options)
+            .await?;
+        Ok(order)
+    }
+
+    /// Like [`create_market_order`](Self::create_market_order), also returning the quote
+    /// the order was priced from.
+    #[tracing::instrument(skip_all, fields(token_id = %order_args.token_id, amount = %order_args.amount))]
+    pub async fn create_market_order_with_quote(
+        &self,
+        order_args: &MarketOrderArgs,
+        extras: Option<ExtraOrderArgs>,
+        options: Option<&CreateOrderOptions>,
+    ) -> ClientResult<(SignedOrderRequest, MarketOrderQuote)> {
         let (_, chain_id) = self.get_l1_parameters();
 
         let create_order_options = self
             .get_filled_order_options(order_args.token_id.as_ref(), options)
             .await?;
 
-        let extras = extras.unwrap_or_default();
-        let price = self
-            .calculate_market_price(&order_args.token_id, Side::BUY, order_args.amount)
+        let mut extras = extras.unwrap_or_default();
+        let fee_rate_bps = self
+            .resolve_fee_rate(&order_args.token_id, extras.fee_rate_bps)
             .await?;
-        if !self.is_price_in_range(
+        extras.fee_rate_bps = Some(fee_rate_bps);
+        let quote = self.quote_market_order(order_args).await?;
+        let price = quote.worst_price;
+        self.validate_order(
+            &order_args.token_id,
             price,
+            quote.size,
             create_order_options.tick_size.expect("Should be filled"),
-        ) {
-            return Err(anyhow!("Price is not in range of tick_size"));
-        }
+            0,
+            0,
+            fee_rate_bps,
+        )
+        .await?;
+        self.check_risk(&order_args.token_id, Side::BUY, price, quote.size)
+            .await?;
 
-        self.order_builder
+        let order = self
+            .order_builder
             .as_ref()
             .expect("OrderBuilder not set")
             .create_market_order(chain_id, order_args, price, &extras, create_order_options)
+            .await?;
+        Ok((order, quote))
     }
 
+    #[tracing::instrument(skip_all, fields(token_id = %order.token_id, side = %order.side, order_type = order_type.as_str()))]
     pub async fn post_order(
         &self,
         order: SignedOrderRequest,
         order_type: OrderType,
     ) -> ClientResult<Value> {
         let (signer, creds) = self.get_l2_parameters();
+        let token_id = order.token_id.clone();
+        let terms = order.terms().ok();
         let body = PostOrder::new(order, creds.api_key.clone(), order_type);
 
         let method = Method::POST;
@@ -572,27 +985,129 @@ impl ClobClient {
 
         let req = self.create_request_with_headers(method, endpoint, headers.into_iter());
 
-        let response = req.json(&body).send().await?;
+        let response = self.send(req.json(&body)).await?;
         let status = response.status();
         let response_text = response.text().await?;
 
         if !status.is_success() {
-            eprintln!("Polymarket API error (status {}): {}", status, response_text);
+            tracing::warn!(status = status.as_u16(), body = %response_text, "order rejected");
+            if response_text.contains(TICK_SIZE_REJECTION) {
+                self.metadata.invalidate_tick_size(&token_id);
+            }
             return Err(anyhow!("API returned error {}: {}", status, response_text));
         }
 
-        serde_json::from_str(&response_text)
-            .map_err(|e| {
-                eprintln!("Failed to parse response as JSON: {}", response_text);
-                anyhow!("JSON parse error: {}. Response was: {}", e, response_text)
-            })
+        let response: Value = serde_json::from_str(&response_text).map_err(|e| {
+            tracing::warn!(error = %e, body = %response_text, "failed to parse order response");
+            anyhow!("JSON parse error: {}. Response was: {}", e, response_text)
+        })?;
+        if let (Some(guard), Some((side, _, size))) = (&self.risk_guard, terms) {
+            guard.on_post_response(&token_id, side, size, &response);
+        }
+        Ok(response)
+    }
+
+    fn note_cancel(&self, response: Value) -> Value {
+        if let Some(guard) = &self.risk_guard {
+            guard.on_cancel_response(&response);
+        }
+        response
     }
 
+    #[tracing::instrument(skip_all, fields(token_id = %order_args.token_id))]
     pub async fn create_and_post_order(&self, order_args: &OrderArgs) -> ClientResult<Value> {
         let order = self.create_order(order_args, None, None, None).await?;
-        self.post_order(order, OrderType::GTC).await
+        self.post_order(order, order_args.time_in_force.order_type())
+            .await
+    }
+
+    /// The expiration to sign for a GTD order ending at `expiry`.
+    ///
+    /// The exchange treats orders as expired [`GTD_EXPIRATION_BUFFER_SECS`] before their
+    /// signed expiration, so the buffer is added on top. Relative expiries are measured
+    /// from the exchange's clock rather than the local one.
+    pub async fn order_expiration(&self, expiry: Expiry) -> ClientResult<u64> {
+        let now = match expiry {
+            Expiry::At(_) => 0,
+            Expiry::After(_) => self.get_server_time().await?,
+        };
+        Ok(gtd_expiration(expiry, now))
     }
 
+    /// Replaces `order_id` with a new order for `order_args`, posted with its time in force.
+    ///
+    /// The new order is signed before anything is sent. With [`ReplaceMode::CancelFirst`] it
+    /// is posted only if the exchange confirms the cancel, so an old order that has already
+    /// filled is not replaced. With [`ReplaceMode::PostFirst`] the old order is only
+    /// cancelled once the new one has been accepted.
+    #[tracing::instrument(skip(self, order_args), fields(token_id = %order_args.token_id, price = %order_args.price, size = %order_args.size))]
+    pub async fn replace_order(
+        &self,
+        order_id: &str,
+        order_args: &OrderArgs,
+        mode: ReplaceMode,
+    ) -> ClientResult<ReplaceOutcome> {
+        let order = self.create_order(order_args, None, None, None).await?;
+        let order_type = order_args.time_in_force.order_type();
+        let mut outcome = ReplaceOutcome::default();
+
+        match mode {
+            ReplaceMode::CancelFirst => {
+                self.replace_cancel(order_id, &mut outcome).await;
+                if outcome.cancelled {
+                    self.replace_post(order, order_type, &mut outcome).await;
+                }
+            }
+            ReplaceMode::PostFirst => {
+                self.replace_post(order, order_type, &mut outcome).await;
+                if outcome.posted.is_some() {
+                    self.replace_cancel(order_id, &mut outcome).await;
+                }
+            }
+        }
+        if !outcome.is_complete() {
+            tracing::warn!(?outcome, "replace incomplete");
+        }
+        Ok(outcome)
+    }
+
+    async fn replace_cancel(&self, order_id: &str, outcome: &mut ReplaceOutcome) {
+        match self.cancel(order_id).await {
+            Ok(resp) => {
+                let canceled = resp["canceled"]
+                    .as_array()
+                    .is_some_and(|ids| ids.iter().any(|id| id == order_id));
+                outcome.cancelled = canceled;
+                if !canceled {
+                    outcome.cancel_error = Some(match &resp["not_canceled"][order_id] {
+                        Value::String(reason) => reason.clone(),
+                        _ => format!("Order was not cancelled: {resp}"),
+                    });
+                }
+            }
+            Err(e) => outcome.cancel_error = Some(e.to_string()),
+        }
+    }
+
+    async fn replace_post(
+        &self,
+        order: SignedOrderRequest,
+        order_type: OrderType,
+        outcome: &mut ReplaceOutcome,
+    ) {
+        match self.post_order(order, order_type).await {
+            Ok(resp) if resp["success"] == true => outcome.posted = Some(resp),
+            Ok(resp) => {
+                let msg = resp["errorMsg"]
+                    .as_str()
+                    .unwrap_or("Order was not accepted");
+                outcome.post_error = Some(msg.to_owned());
+            }
+            Err(e) => outcome.post_error = Some(e.to_string()),
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
     pub async fn cancel(&self, order_id: &str) -> ClientResult<Value> {
         let (signer, creds) = self.get_l2_parameters();
         let body = HashMap::from([("orderID", order_id)]);
@@ -604,9 +1119,11 @@ impl ClobClient {
 
         let req = self.create_request_with_headers(method, endpoint, headers.into_iter());
 
-        Ok(req.json(&body).send().await?.json::<Value>().await?)
+        let response = self.send(req.json(&body)).await?.json::<Value>().await?;
+        Ok(self.note_cancel(response))
     }
 
+    #[tracing::instrument(skip_all, fields(orders = order_ids.len()))]
     pub async fn cancel_orders(&self, order_ids: &[String]) -> ClientResult<Value> {
         let (signer, creds) = self.get_l2_parameters();
         let method = Method::DELETE;
@@ -616,9 +1133,15 @@ impl ClobClient {
 
         let req = self.create_request_with_headers(method, endpoint, headers.into_iter());
 
-        Ok(req.json(order_ids).send().await?.json::<Value>().await?)
+        let response = self
+            .send(req.json(order_ids))
+            .await?
+            .json::<Value>()
+            .await?;
+        Ok(self.note_cancel(response))
     }
 
+    #[tracing::instrument(skip_all)]
     pub async fn cancel_all(&self) -> ClientResult<Value> {
         let (signer, creds) = self.get_l2_parameters();
         let method = Method::DELETE;
@@ -628,9 +1151,11 @@ impl ClobClient {
 
         let req = self.create_request_with_headers(method, endpoint, headers.into_iter());
 
-        Ok(req.send().await?.json::<Value>().await?)
+        let response = self.send(req).await?.json::<Value>().await?;
+        Ok(self.note_cancel(response))
     }
 
+    #[tracing::instrument(skip(self))]
     pub async fn cancel_market_orders(
         &self,
         market: Option<&str>,
@@ -648,9 +1173,11 @@ impl ClobClient {
 
         let req = self.create_request_with_headers(method, endpoint, headers.into_iter());
 
-        Ok(req.json(&body).send().await?.json::<Value>().await?)
+        let response = self.send(req.json(&body)).await?.json::<Value>().await?;
+        Ok(self.note_cancel(response))
     }
 
+    #[tracing::instrument(skip_all, fields(pages = tracing::field::Empty))]
     pub async fn get_orders(
         &self,
         params: Option<&OpenOrderParams>,
@@ -668,19 +1195,14 @@ impl ClobClient {
 
         let mut next_cursor = next_cursor.unwrap_or(INITIAL_CURSOR).to_string();
         let mut output = Vec::new();
+        let mut pages = 0;
         while next_cursor != END_CURSOR {
             let req = self
-                .http_client
-                .request(method.clone(), format!("{}{endpoint}", &self.host))
+                .create_request_with_headers(method.clone(), endpoint, headers.clone().into_iter())
                 .query(&query_params)
-                .query(&["next_cursor", &next_cursor]);
-
-            let r = headers
-                .clone()
-                .into_iter()
-                .fold(req, |r, (k, v)| r.header(HeaderName::from_static(k), v));
+                .query(&[("next_cursor", &next_cursor)]);
 
-            let resp = r.send().await?.json::<Value>().await?;
+            let resp = self.send(req).await?.json::<Value>().await?;
             let new_cursor = resp["next_cursor"]
                 .as_str()
                 .expect("Failed to parse next cursor")
@@ -691,11 +1213,15 @@ impl ClobClient {
             let results = resp["data"].clone();
             let o = serde_json::from_value::<Vec<OpenOrder>>(results)
                 .expect("Failed to parse data from order response");
+            pages += 1;
+            tracing::debug!(page = pages, orders = o.len(), next_cursor = %next_cursor, "fetched orders page");
             output.extend(o);
         }
+        tracing::Span::current().record("pages", pages);
         Ok(output)
     }
 
+    #[tracing::instrument(skip(self))]
     pub async fn get_order(&self, order_id: &str) -> ClientResult<Option<OpenOrder>> {
         let (signer, creds) = self.get_l2_parameters();
         let method = Method::GET;
@@ -706,36 +1232,35 @@ impl ClobClient {
         let req = self.create_request_with_headers(method, endpoint, headers.into_iter());
 
         // API returns null for non-existent orders, so use Option<OpenOrder>
-        Ok(req.send().await?.json::<Option<OpenOrder>>().await?)
+        Ok(self.send(req).await?.json::<Option<OpenOrder>>().await?)
     }
 
+    #[tracing::instrument(skip(self))]
     pub async fn get_last_trade_price(&self, token_id: &str) -> ClientResult<Value> {
-        Ok(self
+        let req = self
             .http_client
             .get(format!("{}/last-trade-price", &self.host))
-            .query(&[("token_id", token_id)])
-            .send()
-            .await?
-            .json::<Value>()
-            .await?)
+            .query(&[("token_id", token_id)]);
+
+        Ok(self.send(req).await?.json::<Value>().await?)
     }
 
+    #[tracing::instrument(skip_all, fields(tokens = token_ids.len()))]
     pub async fn get_last_trade_prices(&self, token_ids: &[String]) -> ClientResult<Value> {
         let v = token_ids
             .iter()
             .map(|b| HashMap::from([("token_id", b.clone())]))
             .collect::<Vec<HashMap<&str, String>>>();
 
-        Ok(self
+        let req = self
             .http_client
             .post(format!("{}/last-trades-prices", &self.host))
-            .json(&v)
-            .send()
-            .await?
-            .json::<Value>()
-            .await?)
+            .json(&v);
+
+        Ok(self.send(req).await?.json::<Value>().await?)
     }
 
+    #[tracing::instrument(skip_all, fields(pages = tracing::field::Empty))]
     pub async fn get_trades(
         &self,
         trade_params: Option<&TradeParams>,
@@ -754,19 +1279,14 @@ impl ClobClient {
         let mut next_cursor = next_cursor.unwrap_or(INITIAL_CURSOR).to_string();
 
         let mut output = Vec::new();
+        let mut pages = 0;
         while next_cursor != END_CURSOR {
             let req = self
-                .http_client
-                .request(method.clone(), format!("{}{endpoint}", &self.host))
+                .create_request_with_headers(method.clone(), endpoint, headers.clone().into_iter())
                 .query(&query_params)
-                .query(&["next_cursor", &next_cursor]);
-
-            let r = headers
-                .clone()
-                .into_iter()
-                .fold(req, |r, (k, v)| r.header(HeaderName::from_static(k), v));
+                .query(&[("next_cursor", &next_cursor)]);
 
-            let resp = r.send().await?.json::<Value>().await?;
+            let resp = self.send(req).await?.json::<Value>().await?;
             let new_cursor = resp["next_cursor"]
                 .as_str()
                 .expect("Failed to parse next cursor")
@@ -775,11 +1295,15 @@ impl ClobClient {
             next_cursor = new_cursor;
 
             let results = resp["data"].clone();
+            pages += 1;
+            tracing::debug!(page = pages, next_cursor = %next_cursor, "fetched trades page");
             output.push(results);
         }
+        tracing::Span::current().record("pages", pages);
         Ok(output)
     }
 
+    #[tracing::instrument(skip_all)]
     pub async fn get_notifications(&self) -> ClientResult<Value> {
         let (signer, creds) = self.get_l2_parameters();
 
@@ -789,21 +1313,23 @@ impl ClobClient {
 
         let req = self.create_request_with_headers(method, endpoint, headers.into_iter());
 
-        Ok(req
-            .query(&[(
-                "signature_type",
-                &self
-                    .order_builder
-                    .as_ref()
-                    .expect("Orderbuilder not set")
-                    .get_sig_type(),
-            )])
-            .send()
+        Ok(self
+            .send(
+                req.query(&[(
+                    "signature_type",
+                    &self
+                        .order_builder
+                        .as_ref()
+                        .expect("Orderbuilder not set")
+                        .get_sig_type(),
+                )]),
+            )
             .await?
             .json::<Value>()
             .await?)
     }
 
+    #[tracing::instrument(skip_all)]
     pub async fn drop_notifications(&self, ids: &[String]) -> ClientResult<Value> {
         let (signer, creds) = self.get_l2_parameters();
 
@@ -813,14 +1339,14 @@ impl ClobClient {
 
         let req = self.create_request_with_headers(method, endpoint, headers.into_iter());
 
-        Ok(req
-            .query(&[("ids", ids.join(","))])
-            .send()
+        Ok(self
+            .send(req.query(&[("ids", ids.join(","))]))
             .await?
             .json::<Value>()
             .await?)
     }
 
+    #[tracing::instrument(skip_all)]
     pub async fn get_balance_allowance(
         &self,
         params: Option<BalanceAllowanceParams>,
@@ -844,14 +1370,14 @@ impl ClobClient {
         let headers = create_l2_headers::<Value>(signer, creds, method.as_str(), endpoint, None)?;
 
         let req = self.create_request_with_headers(method, endpoint, headers.into_iter());
-        Ok(req
-            .query(&query_params)
-            .send()
+        Ok(self
+            .send(req.query(&query_params))
             .await?
             .json::<Value>()
             .await?)
     }
 
+    #[tracing::instrument(skip_all)]
     pub async fn update_balance_allowance(
         &self,
         params: Option<BalanceAllowanceParams>,
@@ -875,14 +1401,14 @@ impl ClobClient {
         let headers = create_l2_headers::<Value>(signer, creds, method.as_str(), endpoint, None)?;
 
         let req = self.create_request_with_headers(method, endpoint, headers.into_iter());
-        Ok(req
-            .query(&query_params)
-            .send()
+        Ok(self
+            .send(req.query(&query_params))
             .await?
             .json::<Value>()
             .await?)
     }
 
+    #[tracing::instrument(skip(self))]
     pub async fn is_order_scoring(&self, order_id: &str) -> ClientResult<bool> {
         let (signer, creds) = self.get_l2_parameters();
 
@@ -891,9 +1417,8 @@ impl ClobClient {
         let headers = create_l2_headers::<Value>(signer, creds, method.as_str(), endpoint, None)?;
         let req = self.create_request_with_headers(method, endpoint, headers.into_iter());
 
-        Ok(req
-            .query(&[("order_id", order_id)])
-            .send()
+        Ok(self
+            .send(req.query(&[("order_id", order_id)]))
             .await?
             .json::<Value>()
             .await?["scoring"]
@@ -901,6 +1426,7 @@ impl ClobClient {
             .expect("Unknown scoring value"))
     }
 
+    #[tracing::instrument(skip_all, fields(orders = order_ids.len()))]
     pub async fn are_orders_scoring(
         &self,
         order_ids: &[&str],
@@ -913,92 +1439,94 @@ impl ClobClient {
         let headers = create_l2_headers(signer, creds, method.as_str(), endpoint, Some(order_ids))?;
         let req = self.create_request_with_headers(method, endpoint, headers.into_iter());
 
-        Ok(req
-            .json(order_ids)
-            .send()
+        Ok(self
+            .send(req.json(order_ids))
             .await?
             .json::<HashMap<String, bool>>()
             .await?)
     }
 
-    pub async fn get_sampling_markets(&self, next_cursor: Option<&str>) -> ClientResult<MarketsResponse> {
+    #[tracing::instrument(skip(self))]
+    pub async fn get_sampling_markets(
+        &self,
+        next_cursor: Option<&str>,
+    ) -> ClientResult<MarketsResponse> {
         let next_cursor = next_cursor.unwrap_or(INITIAL_CURSOR);
 
-        Ok(self
+        let req = self
             .http_client
             .get(format!("{}/sampling-markets", &self.host))
-            .query(&[("next_cursor", next_cursor)])
-            .send()
-            .await?
-            .json::<MarketsResponse>()
-            .await?)
+            .query(&[("next_cursor", next_cursor)]);
+
+        Ok(self.send(req).await?.json::<MarketsResponse>().await?)
     }
 
+    #[tracing::instrument(skip(self))]
     pub async fn get_sampling_simplified_markets(
         &self,
         next_cursor: Option<&str>,
     ) -> ClientResult<SimplifiedMarketsResponse> {
         let next_cursor = next_cursor.unwrap_or(INITIAL_CURSOR);
 
-        Ok(self
+        let req = self
             .http_client
             .get(format!("{}/sampling-simplified-markets", &self.host))
-            .query(&[("next_cursor", next_cursor)])
-            .send()
+            .query(&[("next_cursor", next_cursor)]);
+
+        Ok(self
+            .send(req)
             .await?
             .json::<SimplifiedMarketsResponse>()
             .await?)
     }
 
+    #[tracing::instrument(skip(self))]
     pub async fn get_markets(&self, next_cursor: Option<&str>) -> ClientResult<MarketsResponse> {
         let next_cursor = next_cursor.unwrap_or(INITIAL_CURSOR);
 
-        Ok(self
+        let req = self
             .http_client
             .get(format!("{}/markets", &self.host))
-            .query(&[("next_cursor", next_cursor)])
-            .send()
-            .await?
-            .json::<MarketsResponse>()
-            .await?)
+            .query(&[("next_cursor", next_cursor)]);
+
+        Ok(self.send(req).await?.json::<MarketsResponse>().await?)
     }
 
+    #[tracing::instrument(skip(self))]
     pub async fn get_simplified_markets(
         &self,
         next_cursor: Option<&str>,
     ) -> ClientResult<SimplifiedMarketsResponse> {
         let next_cursor = next_cursor.unwrap_or(INITIAL_CURSOR);
 
-        Ok(self
+        let req = self
             .http_client
             .get(format!("{}/simplified-markets", &self.host))
-            .query(&[("next_cursor", next_cursor)])
-            .send()
+            .query(&[("next_cursor", next_cursor)]);
+
+        Ok(self
+            .send(req)
             .await?
             .json::<SimplifiedMarketsResponse>()
             .await?)
     }
 
+    #[tracing::instrument(skip(self))]
     pub async fn get_market(&self, condition_id: &str) -> ClientResult<Market> {
-        Ok(self
+        let req = self
             .http_client
-            .get(format!("{}/markets/{condition_id}", &self.host))
-            .send()
-            .await?
-            .json::<Market>()
-            .await?)
+            .get(format!("{}/markets/{condition_id}", &self.host));
+
+        Ok(self.send(req).await?.json::<Market>().await?)
     }
 
+    #[tracing::instrument(skip(self))]
     pub async fn get_market_trades_events(&self, condition_id: &str) -> ClientResult<Value> {
-        Ok(self
-            .http_client
-            .get(format!(
-                "{}/live-activity/events/{condition_id}",
-                &self.host
-            ))
-            .send()
-            .await?
-            .json::<Value>()
-            .await?)
+        let req = self.http_client.get(format!(
+            "{}/live-activity/events/{condition_id}",
+            &self.host
+        ));
+
+        Ok(self.send(req).await?.json::<Value>().await?)
     }
 }
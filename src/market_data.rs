@@ -0,0 +1,426 @@
+//! Recording market data to disk and reading it back.
+//!
+//! Files are gzip-compressed newline-delimited JSON, one per UTC day, named
+//! `YYYY-MM-DD.ndjson.gz`. Every batch of records is appended as its own gzip member,
+//! so a file stays readable up to the last complete batch if the process dies mid-write.
+
+use crate::utils::get_current_unix_time_millis;
+use crate::{ClientResult, ClobClient, MarketEvent, OrderBookSummary, OrderSummary, Side};
+use anyhow::Context;
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Lines, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+const EXTENSION: &str = ".ndjson.gz";
+
+/// A market event with the local time it was received, in milliseconds since the Unix epoch.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedEvent {
+    pub received_at: u64,
+    #[serde(flatten)]
+    pub event: MarketEvent,
+}
+
+/// `YYYY-MM-DD` in UTC for a timestamp in milliseconds.
+fn utc_date(millis: u64) -> String {
+    // Civil-from-days, see https://howardhinnant.github.io/date_algorithms.html
+    let z = (millis / 86_400_000) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// Appends records to daily files in a directory.
+pub struct MarketDataWriter {
+    dir: PathBuf,
+    day: String,
+    file: Option<File>,
+}
+
+impl MarketDataWriter {
+    /// Writes into `dir`, creating it if needed.
+    pub fn create(dir: impl AsRef<Path>) -> ClientResult<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        Ok(MarketDataWriter {
+            dir,
+            day: String::new(),
+            file: None,
+        })
+    }
+
+    /// Appends `records` to the file for the day each was received on.
+    pub fn write(&mut self, records: &[RecordedEvent]) -> ClientResult<()> {
+        for batch in records.chunk_by(|a, b| utc_date(a.received_at) == utc_date(b.received_at)) {
+            let day = utc_date(batch[0].received_at);
+            if self.file.is_none() || self.day != day {
+                let path = self.dir.join(format!("{day}{EXTENSION}"));
+                let file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&path)
+                    .with_context(|| format!("Failed to open {}", path.display()))?;
+                self.file = Some(file);
+                self.day = day;
+            }
+
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            for record in batch {
+                serde_json::to_writer(&mut encoder, record)?;
+                encoder.write_all(b"\n")?;
+            }
+            let member = encoder.finish()?;
+            let file = self.file.as_mut().expect("File opened above");
+            file.write_all(&member)
+                .context("Failed to write market data")?;
+            file.flush()?;
+        }
+        Ok(())
+    }
+}
+
+/// Iterates the records in one file or in every daily file of a directory, in date order.
+pub struct MarketDataReader {
+    files: VecDeque<PathBuf>,
+    lines: Option<Lines<BufReader<MultiGzDecoder<File>>>>,
+}
+
+impl MarketDataReader {
+    pub fn open(path: impl AsRef<Path>) -> ClientResult<Self> {
+        let mut reader = MarketDataReader {
+            files: VecDeque::from([path.as_ref().to_path_buf()]),
+            lines: None,
+        };
+        reader.next_file()?;
+        Ok(reader)
+    }
+
+    pub fn open_dir(dir: impl AsRef<Path>) -> ClientResult<Self> {
+        let dir = dir.as_ref();
+        let mut files: Vec<_> = fs::read_dir(dir)
+            .with_context(|| format!("Failed to read {}", dir.display()))?
+            .filter_map(|entry| Some(entry.ok()?.path()))
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.ends_with(EXTENSION))
+            })
+            .collect();
+        files.sort();
+        let mut reader = MarketDataReader {
+            files: files.into(),
+            lines: None,
+        };
+        reader.next_file()?;
+        Ok(reader)
+    }
+
+    /// Opens the next file, leaving `lines` empty when there is none.
+    fn next_file(&mut self) -> ClientResult<()> {
+        self.lines = None;
+        if let Some(path) = self.files.pop_front() {
+            let file =
+                File::open(&path).with_context(|| format!("Failed to open {}", path.display()))?;
+            self.lines = Some(BufReader::new(MultiGzDecoder::new(file)).lines());
+        }
+        Ok(())
+    }
+}
+
+impl Iterator for MarketDataReader {
+    type Item = ClientResult<RecordedEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.lines.as_mut()?.next() {
+                Some(Ok(line)) if line.trim().is_empty() => continue,
+                Some(Ok(line)) => {
+                    return Some(
+                        serde_json::from_str(&line).context("Malformed market data record"),
+                    )
+                }
+                Some(Err(e)) => {
+                    self.lines = None;
+                    return Some(Err(e.into()));
+                }
+                None => {
+                    if let Err(e) = self.next_file() {
+                        return Some(Err(e));
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RecorderConfig {
+    pub poll_interval: Duration,
+    /// How often a full snapshot is written instead of deltas. Each day's file also
+    /// starts with a snapshot of every book, so files can be read on their own.
+    pub snapshot_interval: Duration,
+}
+
+impl Default for RecorderConfig {
+    fn default() -> Self {
+        RecorderConfig {
+            poll_interval: Duration::from_secs(1),
+            snapshot_interval: Duration::from_secs(300),
+        }
+    }
+}
+
+/// Price level changes that turn `prev` into `next`, timestamped with `next`.
+fn book_deltas(prev: &OrderBookSummary, next: &OrderBookSummary) -> Vec<MarketEvent> {
+    let mut events = Vec::new();
+    for (side, old, new) in [
+        (Side::BUY, &prev.bids, &next.bids),
+        (Side::SELL, &prev.asks, &next.asks),
+    ] {
+        let levels = |l: &[OrderSummary]| -> BTreeMap<Decimal, Decimal> {
+            l.iter().map(|l| (l.price, l.size)).collect()
+        };
+        let (old, new) = (levels(old), levels(new));
+        let removed = old
+            .keys()
+            .filter(|p| !new.contains_key(p))
+            .map(|p| (*p, Decimal::ZERO));
+        let changed = new
+            .iter()
+            .filter(|(p, s)| old.get(p) != Some(s))
+            .map(|(p, s)| (*p, *s));
+        for (price, size) in removed.chain(changed) {
+            events.push(MarketEvent::PriceChange {
+                asset_id: next.asset_id.clone(),
+                timestamp: next.timestamp,
+                side,
+                price,
+                size,
+            });
+        }
+    }
+    events
+}
+
+/// Polls books and last trade prices for a set of tokens and records every change.
+pub struct MarketRecorder {
+    token_ids: Vec<String>,
+    config: RecorderConfig,
+    writer: MarketDataWriter,
+    day: String,
+    /// Last book seen per token and when it was last written as a snapshot.
+    books: HashMap<String, (OrderBookSummary, u64)>,
+    last_trades: HashMap<String, (Decimal, Side)>,
+}
+
+impl MarketRecorder {
+    pub fn new(
+        dir: impl AsRef<Path>,
+        token_ids: Vec<String>,
+        config: RecorderConfig,
+    ) -> ClientResult<Self> {
+        Ok(MarketRecorder {
+            token_ids,
+            config,
+            writer: MarketDataWriter::create(dir)?,
+            day: String::new(),
+            books: HashMap::new(),
+            last_trades: HashMap::new(),
+        })
+    }
+
+    /// Fetches every book and last trade price once and writes what changed.
+    /// Returns the number of records written.
+    pub async fn poll(&mut self, client: &ClobClient) -> ClientResult<usize> {
+        let books = client.get_order_books(&self.token_ids).await?;
+        let last_trades = client.get_last_trade_prices(&self.token_ids).await?;
+        self.record(books, &last_trades, get_current_unix_time_millis())
+    }
+
+    /// Polls every `poll_interval` until writing to disk fails. Request errors are
+    /// logged and retried on the next tick.
+    pub async fn run(&mut self, client: &ClobClient) -> ClientResult<()> {
+        let mut interval = tokio::time::interval(self.config.poll_interval);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            let books = client.get_order_books(&self.token_ids).await;
+            let last_trades = client.get_last_trade_prices(&self.token_ids).await;
+            match (books, last_trades) {
+                (Ok(books), Ok(last_trades)) => {
+                    let written =
+                        self.record(books, &last_trades, get_current_unix_time_millis())?;
+                    tracing::debug!(written, "recorded market data");
+                }
+                (Err(e), _) | (_, Err(e)) => {
+                    tracing::warn!(error = %e, "failed to poll market data");
+                }
+            }
+        }
+    }
+
+    fn record(
+        &mut self,
+        books: Vec<OrderBookSummary>,
+        last_trades: &Value,
+        received_at: u64,
+    ) -> ClientResult<usize> {
+        let day = utc_date(received_at);
+        let new_day = day != self.day;
+        self.day = day;
+        // Each day's file has to replay on its own, so it starts from full state.
+        if new_day {
+            self.last_trades.clear();
+        }
+        let snapshot_interval = self.config.snapshot_interval.as_millis() as u64;
+
+        let mut events = Vec::new();
+        for book in books {
+            match self.books.get_mut(&book.asset_id) {
+                Some((prev, snapshot_at))
+                    if !new_day && received_at < *snapshot_at + snapshot_interval =>
+                {
+                    if prev.hash != book.hash {
+                        events.extend(book_deltas(prev, &book));
+                        *prev = book;
+                    }
+                }
+                _ => {
+                    events.push(MarketEvent::Book(book.clone()));
+                    self.books
+                        .insert(book.asset_id.clone(), (book, received_at));
+                }
+            }
+        }
+
+        for item in last_trades.as_array().into_iter().flatten() {
+            let (Some(asset_id), Some(price), Ok(side)) = (
+                item["token_id"].as_str(),
+                item["price"]
+                    .as_str()
+                    .and_then(|p| Decimal::from_str(p).ok()),
+                serde_json::from_value::<Side>(item["side"].clone()),
+            ) else {
+                continue;
+            };
+            if self.last_trades.get(asset_id) != Some(&(price, side)) {
+                self.last_trades.insert(asset_id.to_owned(), (price, side));
+                events.push(MarketEvent::LastTradePrice {
+                    asset_id: asset_id.to_owned(),
+                    timestamp: received_at,
+                    side,
+                    price,
+                });
+            }
+        }
+
+        let records: Vec<_> = events
+            .into_iter()
+            .map(|event| RecordedEvent { received_at, event })
+            .collect();
+        self.writer.write(&records)?;
+        Ok(records.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn book(hash: &str, bids: &[(i64, i64)], asks: &[(i64, i64)]) -> OrderBookSummary {
+        let levels = |l: &[(i64, i64)]| {
+            l.iter()
+                .map(|(p, s)| OrderSummary {
+                    price: Decimal::new(*p, 2),
+                    size: Decimal::from(*s),
+                })
+                .collect()
+        };
+        OrderBookSummary {
+            market: "0xmarket".into(),
+            asset_id: "1".into(),
+            hash: hash.into(),
+            timestamp: 1_700_000_000_000,
+            bids: levels(bids),
+            asks: levels(asks),
+        }
+    }
+
+    #[test]
+    fn test_utc_date() {
+        assert_eq!(utc_date(0), "1970-01-01");
+        assert_eq!(utc_date(951_825_600_000), "2000-02-29");
+        assert_eq!(utc_date(1_735_689_599_999), "2024-12-31");
+    }
+
+    #[test]
+    fn test_record_deltas_rotate_and_read_back() {
+        let dir = std::env::temp_dir().join(format!("polymarket-md-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let mut recorder = MarketRecorder::new(&dir, vec!["1".into()], Default::default()).unwrap();
+        let day = 1_735_603_200_000; // 2024-12-31T00:00:00Z
+        let trades = json!([{"token_id": "1", "price": "0.5", "side": "BUY"}]);
+
+        let first = book("a", &[(49, 100), (48, 50)], &[(51, 100)]);
+        assert_eq!(recorder.record(vec![first], &trades, day).unwrap(), 2);
+        let same = book("a", &[(49, 100), (48, 50)], &[(51, 100)]);
+        assert_eq!(recorder.record(vec![same], &trades, day + 1).unwrap(), 0);
+        let moved = book("b", &[(49, 80)], &[(51, 100), (52, 10)]);
+        assert_eq!(recorder.record(vec![moved], &trades, day + 2).unwrap(), 3);
+        let next_day = book("b", &[(49, 80)], &[(51, 100), (52, 10)]);
+        assert_eq!(
+            recorder
+                .record(vec![next_day], &trades, day + 86_400_000)
+                .unwrap(),
+            2
+        );
+
+        let reader = MarketDataReader::open_dir(&dir).unwrap();
+        let records: Vec<_> = reader.map(Result::unwrap).collect();
+        let kinds: Vec<_> = records
+            .iter()
+            .map(|r| match &r.event {
+                MarketEvent::Book(_) => "book",
+                MarketEvent::PriceChange { .. } => "delta",
+                MarketEvent::LastTradePrice { .. } => "last_trade",
+                MarketEvent::Trade { .. } => "trade",
+            })
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                "book",
+                "last_trade",
+                "delta",
+                "delta",
+                "delta",
+                "book",
+                "last_trade"
+            ]
+        );
+        assert!(matches!(
+            &records[2].event,
+            MarketEvent::PriceChange { side: Side::BUY, size, .. } if size.is_zero()
+        ));
+        assert!(dir.join("2024-12-31.ndjson.gz").exists());
+        assert!(dir.join("2025-01-01.ndjson.gz").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
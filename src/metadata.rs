@@ -0,0 +1,245 @@
+//! Per-token cache of tick size, neg-risk flag, fee rate and market.
+
+use crate::Market;
+use rust_decimal::Decimal;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// How long cached token metadata stays valid.
+#[derive(Debug, Clone)]
+pub struct MetadataCacheConfig {
+    /// Tick sizes change when a price nears 0 or 1, so this should stay short.
+    pub tick_size_ttl: Duration,
+    pub neg_risk_ttl: Duration,
+    pub fee_rate_ttl: Duration,
+    /// Market details used to validate orders, such as `active` and `minimum_order_size`.
+    pub market_ttl: Duration,
+    /// Best bid and ask used to check post-only orders.
+    pub top_of_book_ttl: Duration,
+    /// Markets listed under each neg-risk event, used by `ArbScanner`. Listing them takes
+    /// a pass over every page of `get_markets`.
+    pub neg_risk_event_ttl: Duration,
+}
+
+impl Default for MetadataCacheConfig {
+    fn default() -> Self {
+        Self {
+            tick_size_ttl: Duration::from_secs(60),
+            neg_risk_ttl: Duration::from_secs(3600),
+            fee_rate_ttl: Duration::from_secs(300),
+            market_ttl: Duration::from_secs(60),
+            top_of_book_ttl: Duration::from_secs(1),
+            neg_risk_event_ttl: Duration::from_secs(600),
+        }
+    }
+}
+
+/// Best bid and best ask.
+type TopOfBook = (Option<Decimal>, Option<Decimal>);
+
+struct Cached<T> {
+    value: T,
+    at: Instant,
+}
+
+#[derive(Default)]
+struct Entry {
+    tick_size: Option<Cached<Decimal>>,
+    neg_risk: Option<Cached<bool>>,
+    fee_rate_bps: Option<Cached<u32>>,
+    top_of_book: Option<Cached<TopOfBook>>,
+    /// Never changes for a token, so it is kept until invalidated.
+    condition_id: Option<String>,
+}
+
+#[derive(Default)]
+pub(crate) struct MetadataCache {
+    config: MetadataCacheConfig,
+    entries: Mutex<HashMap<String, Entry>>,
+    markets: Mutex<HashMap<String, Cached<Market>>>,
+    /// neg_risk_market_id -> condition IDs
+    events: Mutex<HashMap<String, Cached<BTreeSet<String>>>>,
+}
+
+fn fresh<T: Copy>(cached: &Option<Cached<T>>, ttl: Duration) -> Option<T> {
+    cached
+        .as_ref()
+        .filter(|c| c.at.elapsed() < ttl)
+        .map(|c| c.value)
+}
+
+fn cached<T>(value: T) -> Option<Cached<T>> {
+    Some(Cached {
+        value,
+        at: Instant::now(),
+    })
+}
+
+impl MetadataCache {
+    pub(crate) fn set_config(&mut self, config: MetadataCacheConfig) {
+        self.config = config;
+    }
+
+    pub(crate) fn tick_size(&self, token_id: &str) -> Option<Decimal> {
+        let ttl = self.config.tick_size_ttl;
+        fresh(&self.entries().get(token_id)?.tick_size, ttl)
+    }
+
+    pub(crate) fn neg_risk(&self, token_id: &str) -> Option<bool> {
+        let ttl = self.config.neg_risk_ttl;
+        fresh(&self.entries().get(token_id)?.neg_risk, ttl)
+    }
+
+    pub(crate) fn fee_rate_bps(&self, token_id: &str) -> Option<u32> {
+        let ttl = self.config.fee_rate_ttl;
+        fresh(&self.entries().get(token_id)?.fee_rate_bps, ttl)
+    }
+
+    pub(crate) fn top_of_book(&self, token_id: &str) -> Option<TopOfBook> {
+        let ttl = self.config.top_of_book_ttl;
+        fresh(&self.entries().get(token_id)?.top_of_book, ttl)
+    }
+
+    pub(crate) fn condition_id(&self, token_id: &str) -> Option<String> {
+        self.entries().get(token_id)?.condition_id.clone()
+    }
+
+    pub(crate) fn market(&self, condition_id: &str) -> Option<Market> {
+        let markets = self.markets.lock().expect("Metadata cache lock poisoned");
+        markets
+            .get(condition_id)
+            .filter(|c| c.at.elapsed() < self.config.market_ttl)
+            .map(|c| c.value.clone())
+    }
+
+    pub(crate) fn event_markets(&self, event: &str) -> Option<BTreeSet<String>> {
+        let events = self.events.lock().expect("Metadata cache lock poisoned");
+        events
+            .get(event)
+            .filter(|c| c.at.elapsed() < self.config.neg_risk_event_ttl)
+            .map(|c| c.value.clone())
+    }
+
+    pub(crate) fn set_tick_size(&self, token_id: &str, tick_size: Decimal) {
+        self.entry(token_id, |e| e.tick_size = cached(tick_size));
+    }
+
+    pub(crate) fn set_neg_risk(&self, token_id: &str, neg_risk: bool) {
+        self.entry(token_id, |e| e.neg_risk = cached(neg_risk));
+    }
+
+    pub(crate) fn set_fee_rate_bps(&self, token_id: &str, fee_rate_bps: u32) {
+        self.entry(token_id, |e| e.fee_rate_bps = cached(fee_rate_bps));
+    }
+
+    pub(crate) fn set_top_of_book(&self, token_id: &str, top_of_book: TopOfBook) {
+        self.entry(token_id, |e| e.top_of_book = cached(top_of_book));
+    }
+
+    pub(crate) fn set_condition_id(&self, token_id: &str, condition_id: &str) {
+        self.entry(token_id, |e| e.condition_id = Some(condition_id.to_owned()));
+    }
+
+    pub(crate) fn set_market(&self, market: Market) {
+        let mut markets = self.markets.lock().expect("Metadata cache lock poisoned");
+        markets.insert(
+            market.condition_id.clone(),
+            Cached {
+                value: market,
+                at: Instant::now(),
+            },
+        );
+    }
+
+    pub(crate) fn set_event_markets(&self, event: &str, markets: BTreeSet<String>) {
+        let mut events = self.events.lock().expect("Metadata cache lock poisoned");
+        events.insert(
+            event.to_owned(),
+            Cached {
+                value: markets,
+                at: Instant::now(),
+            },
+        );
+    }
+
+    pub(crate) fn invalidate_tick_size(&self, token_id: &str) {
+        self.entry(token_id, |e| e.tick_size = None);
+    }
+
+    pub(crate) fn invalidate(&self, token_id: &str) {
+        let entry = self.entries().remove(token_id);
+        if let Some(condition_id) = entry.and_then(|e| e.condition_id) {
+            self.markets
+                .lock()
+                .expect("Metadata cache lock poisoned")
+                .remove(&condition_id);
+        }
+    }
+
+    pub(crate) fn clear(&self) {
+        self.entries().clear();
+        self.markets
+            .lock()
+            .expect("Metadata cache lock poisoned")
+            .clear();
+        self.events
+            .lock()
+            .expect("Metadata cache lock poisoned")
+            .clear();
+    }
+
+    fn entry(&self, token_id: &str, f: impl FnOnce(&mut Entry)) {
+        f(self.entries().entry(token_id.to_owned()).or_default());
+    }
+
+    fn entries(&self) -> MutexGuard<'_, HashMap<String, Entry>> {
+        self.entries.lock().expect("Metadata cache lock poisoned")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metadata_cache_ttl_and_invalidation() {
+        let mut cache = MetadataCache::default();
+        cache.set_config(MetadataCacheConfig {
+            tick_size_ttl: Duration::ZERO,
+            ..Default::default()
+        });
+        cache.set_tick_size("1", Decimal::new(1, 2));
+        cache.set_neg_risk("1", true);
+        cache.set_fee_rate_bps("1", 1000);
+
+        assert_eq!(cache.tick_size("1"), None);
+        assert_eq!(cache.neg_risk("1"), Some(true));
+        assert_eq!(cache.fee_rate_bps("1"), Some(1000));
+        assert_eq!(cache.neg_risk("2"), None);
+
+        cache.set_config(MetadataCacheConfig::default());
+        cache.set_tick_size("1", Decimal::new(1, 2));
+        cache.invalidate_tick_size("1");
+        assert_eq!(cache.tick_size("1"), None);
+        assert_eq!(cache.neg_risk("1"), Some(true));
+
+        cache.invalidate("1");
+        assert_eq!(cache.neg_risk("1"), None);
+    }
+
+    #[test]
+    fn test_event_markets_ttl() {
+        let mut cache = MetadataCache::default();
+        let markets = BTreeSet::from(["0xa".to_owned(), "0xb".to_owned()]);
+        cache.set_event_markets("0xevent", markets.clone());
+        assert_eq!(cache.event_markets("0xevent"), Some(markets));
+        assert_eq!(cache.event_markets("0xother"), None);
+
+        cache.set_config(MetadataCacheConfig {
+            neg_risk_event_ttl: Duration::ZERO,
+            ..Default::default()
+        });
+        assert_eq!(cache.event_markets("0xevent"), None);
+    }
+}
@@ -0,0 +1,116 @@
+use crate::ClientResult;
+use async_trait::async_trait;
+use reqwest::{Client, Request, Response};
+use std::sync::Arc;
+
+/// Hook that wraps every HTTP request sent by [`ClobClient`](crate::ClobClient).
+///
+/// Requests reach the middleware fully built, with L1/L2 auth headers already attached.
+/// A middleware can modify the request, call `next.run(req)` to pass it down the chain and
+/// inspect or replace the response, or return a response of its own without calling `next`.
+/// Middlewares run in the order they were added.
+#[async_trait]
+pub trait Middleware: Send + Sync {
+    async fn handle(&self, req: Request, next: Next<'_>) -> ClientResult<Response>;
+}
+
+#[async_trait]
+impl<M: Middleware + ?Sized> Middleware for Arc<M> {
+    async fn handle(&self, req: Request, next: Next<'_>) -> ClientResult<Response> {
+        (**self).handle(req, next).await
+    }
+}
+
+/// The remainder of the middleware chain, ending with the HTTP client.
+pub struct Next<'a> {
+    http_client: &'a Client,
+    middlewares: &'a [Arc<dyn Middleware>],
+}
+
+impl<'a> Next<'a> {
+    pub(crate) fn new(http_client: &'a Client, middlewares: &'a [Arc<dyn Middleware>]) -> Self {
+        Next {
+            http_client,
+            middlewares,
+        }
+    }
+
+    pub async fn run(self, req: Request) -> ClientResult<Response> {
+        match self.middlewares.split_first() {
+            Some((middleware, rest)) => {
+                middleware
+                    .handle(req, Next::new(self.http_client, rest))
+                    .await
+            }
+            None => Ok(self.http_client.execute(req).await?),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ApiCreds, ClobClient};
+    use std::sync::Mutex;
+
+    const TEST_KEY: &str = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+    struct AddHeader;
+
+    #[async_trait]
+    impl Middleware for AddHeader {
+        async fn handle(&self, mut req: Request, next: Next<'_>) -> ClientResult<Response> {
+            req.headers_mut().insert("x-desk", "mm-1".parse().unwrap());
+            next.run(req).await
+        }
+    }
+
+    #[derive(Default)]
+    struct Canned {
+        seen: Mutex<Vec<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl Middleware for Canned {
+        async fn handle(&self, req: Request, _next: Next<'_>) -> ClientResult<Response> {
+            let names = req.headers().keys().map(|k| k.to_string()).collect();
+            self.seen.lock().unwrap().push(names);
+            let resp = http::Response::builder()
+                .status(200)
+                .body(r#"{"apiKeys": ["k1"]}"#)
+                .unwrap();
+            Ok(resp.into())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_middleware_sees_auth_headers_and_short_circuits() {
+        let canned = Arc::new(Canned::default());
+        let mut client = ClobClient::with_l2_headers(
+            "http://127.0.0.1:1",
+            TEST_KEY,
+            137,
+            ApiCreds::new(
+                "key",
+                "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=",
+                "pass",
+            ),
+        );
+        client.add_middleware(AddHeader);
+        client.add_middleware(canned.clone());
+
+        let keys = client.get_api_keys().await.unwrap();
+        assert_eq!(keys, vec!["k1".to_string()]);
+
+        let seen = canned.seen.lock().unwrap();
+        assert_eq!(seen.len(), 1);
+        for header in [
+            "poly_api_key",
+            "poly_signature",
+            "poly_passphrase",
+            "x-desk",
+        ] {
+            assert!(seen[0].iter().any(|h| h == header), "missing {header}");
+        }
+    }
+}
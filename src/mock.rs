@@ -0,0 +1,989 @@
+//! In-process mock of the CLOB HTTP API for integration tests.
+//!
+//! [`MockClob`] serves the public market data endpoints, order placement and
+//! cancellation, and the paginated `/data/orders` and `/data/trades` endpoints.
+//! Authenticated endpoints check the L1 EIP-712 signature or the L2 API key,
+//! passphrase and HMAC signature exactly like the real API and answer 401 otherwise.
+
+use crate::eth_utils::clob_auth_payload;
+use crate::headers::{
+    POLY_ADDR_HEADER, POLY_API_KEY_HEADER, POLY_NONCE_HEADER, POLY_PASS_HEADER, POLY_SIG_HEADER,
+    POLY_TS_HEADER,
+};
+use crate::utils::{build_hmac_signature_raw, get_current_unix_time_secs};
+use crate::{ApiCreds, ClientResult, Side};
+use alloy_primitives::{Address, Signature, U256};
+use alloy_sol_types::SolStruct;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use http_body_util::{BodyExt, Full};
+use hyper::body::{Bytes, Incoming};
+use hyper::http::request::Parts;
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use reqwest::Url;
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+
+const END_CURSOR: &str = "LTE=";
+const MOCK_SECRET: &str = "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=";
+
+#[derive(Default)]
+struct MockState {
+    api_key: String,
+    secret: String,
+    passphrase: String,
+    page_size: usize,
+    clock_offset: i64,
+    requests: HashMap<String, usize>,
+    next_order_id: u64,
+    books: HashMap<String, Value>,
+    tick_sizes: HashMap<String, Decimal>,
+    neg_risk: HashMap<String, bool>,
+    fee_rates: HashMap<String, u32>,
+    markets: HashMap<String, Value>,
+    orders: Vec<Value>,
+    trades: Vec<Value>,
+}
+
+/// A running mock CLOB server. The server stops when this value is dropped.
+pub struct MockClob {
+    addr: SocketAddr,
+    state: Arc<Mutex<MockState>>,
+    task: JoinHandle<()>,
+}
+
+impl Drop for MockClob {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl MockClob {
+    /// Binds to a random local port and starts serving.
+    pub async fn start() -> ClientResult<Self> {
+        let state = Arc::new(Mutex::new(MockState {
+            api_key: "00000000-0000-0000-0000-000000000000".to_owned(),
+            secret: MOCK_SECRET.to_owned(),
+            passphrase: "mock-passphrase".to_owned(),
+            page_size: 100,
+            ..Default::default()
+        }));
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+
+        let server_state = state.clone();
+        let task = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let state = server_state.clone();
+                tokio::spawn(async move {
+                    let service = service_fn(move |req| handle(state.clone(), req));
+                    let _ = http1::Builder::new()
+                        .serve_connection(TokioIo::new(stream), service)
+                        .await;
+                });
+            }
+        });
+
+        Ok(MockClob { addr, state, task })
+    }
+
+    /// Base URL to pass as the client host.
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// The only credentials accepted for L2 requests; also returned by the L1 key endpoints.
+    pub fn api_creds(&self) -> ApiCreds {
+        let state = self.state();
+        ApiCreds::new(
+            &state.api_key,
+            state.secret.as_str(),
+            state.passphrase.as_str(),
+        )
+    }
+
+    /// Number of items per page on paginated endpoints.
+    pub fn set_page_size(&self, page_size: usize) {
+        self.state().page_size = page_size.max(1);
+    }
+
+    /// Shifts the time served by `/time` by `secs` from the local clock.
+    pub fn set_clock_offset(&self, secs: i64) {
+        self.state().clock_offset = secs;
+    }
+
+    /// Sets the book for `token_id`. Levels are `(price, size)` and are served in the order given.
+    /// Also creates an active market `market`, with a minimum order size of 5, listing the token.
+    pub fn set_book(
+        &self,
+        token_id: &str,
+        market: &str,
+        bids: &[(Decimal, Decimal)],
+        asks: &[(Decimal, Decimal)],
+    ) {
+        let levels = |l: &[(Decimal, Decimal)]| {
+            l.iter()
+                .map(
+                    |(price, size)| json!({ "price": price.to_string(), "size": size.to_string() }),
+                )
+                .collect::<Vec<_>>()
+        };
+        let book = json!({
+            "market": market,
+            "asset_id": token_id,
+            "hash": format!("{:x}", get_current_unix_time_secs()),
+            "timestamp": get_current_unix_time_secs().to_string(),
+            "bids": levels(bids),
+            "asks": levels(asks),
+        });
+        let mut state = self.state();
+        state.books.insert(token_id.to_owned(), book);
+        let market = state
+            .markets
+            .entry(market.to_owned())
+            .or_insert_with(|| default_market(market));
+        let tokens = market["tokens"].as_array_mut().expect("tokens is an array");
+        if !tokens.iter().any(|t| t["token_id"] == token_id) {
+            if let Some(free) = tokens.iter_mut().find(|t| t["token_id"] == "") {
+                free["token_id"] = json!(token_id);
+            }
+        }
+    }
+
+    pub fn set_market_status(&self, market: &str, active: bool, closed: bool) {
+        if let Some(m) = self.state().markets.get_mut(market) {
+            m["active"] = json!(active);
+            m["closed"] = json!(closed);
+        }
+    }
+
+    /// Lists `market` as one outcome of the neg-risk event `event`.
+    pub fn set_neg_risk_market(&self, market: &str, event: &str) {
+        if let Some(m) = self.state().markets.get_mut(market) {
+            m["neg_risk"] = json!(true);
+            m["neg_risk_market_id"] = json!(event);
+        }
+    }
+
+    pub fn set_minimum_order_size(&self, market: &str, size: Decimal) {
+        if let Some(m) = self.state().markets.get_mut(market) {
+            m["minimum_order_size"] = json!(size.to_string());
+        }
+    }
+
+    pub fn set_tick_size(&self, token_id: &str, tick_size: Decimal) {
+        self.state()
+            .tick_sizes
+            .insert(token_id.to_owned(), tick_size);
+    }
+
+    pub fn set_neg_risk(&self, token_id: &str, neg_risk: bool) {
+        self.state().neg_risk.insert(token_id.to_owned(), neg_risk);
+    }
+
+    pub fn set_fee_rate_bps(&self, token_id: &str, fee_rate_bps: u32) {
+        self.state()
+            .fee_rates
+            .insert(token_id.to_owned(), fee_rate_bps);
+    }
+
+    /// Adds a trade to be served by `/data/trades`.
+    pub fn add_trade(&self, trade: Value) {
+        self.state().trades.push(trade);
+    }
+
+    /// Number of requests received for `path`.
+    pub fn request_count(&self, path: &str) -> usize {
+        self.state().requests.get(path).copied().unwrap_or_default()
+    }
+
+    /// All orders received so far, in the `/data/order` format, including cancelled ones.
+    pub fn orders(&self) -> Vec<Value> {
+        self.state().orders.clone()
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, MockState> {
+        self.state.lock().expect("Mock state lock poisoned")
+    }
+}
+
+type MockResponse = Response<Full<Bytes>>;
+
+fn default_market(condition_id: &str) -> Value {
+    json!({
+        "condition_id": condition_id,
+        "tokens": [{ "token_id": "", "outcome": "Yes" }, { "token_id": "", "outcome": "No" }],
+        "rewards": { "rates": null, "min_size": 0, "max_spread": 0 },
+        "min_incentive_size": null,
+        "max_incentive_spread": null,
+        "active": true,
+        "closed": false,
+        "question_id": "",
+        "minimum_order_size": 5,
+        "minimum_tick_size": 0.01,
+        "description": "",
+        "question": "",
+        "market_slug": "",
+        "seconds_delay": 0,
+        "icon": "",
+        "fpmm": "",
+    })
+}
+
+fn reply(status: StatusCode, body: Value) -> MockResponse {
+    Response::builder()
+        .status(status)
+        .header("content-type", "application/json")
+        .body(Full::new(Bytes::from(body.to_string())))
+        .expect("Valid response")
+}
+
+fn error(status: StatusCode, msg: &str) -> MockResponse {
+    reply(status, json!({ "error": msg }))
+}
+
+fn header<'a>(req: &'a Parts, name: &str) -> Option<&'a str> {
+    req.headers.get(name).and_then(|v| v.to_str().ok())
+}
+
+fn query(req: &Parts) -> HashMap<String, String> {
+    Url::parse(&format!("http://localhost{}", req.uri))
+        .map(|u| u.query_pairs().into_owned().collect())
+        .unwrap_or_default()
+}
+
+fn check_l1(req: &Parts) -> bool {
+    let (Some(address), Some(signature), Some(timestamp), Some(nonce)) = (
+        header(req, POLY_ADDR_HEADER).and_then(|a| Address::from_str(a).ok()),
+        header(req, POLY_SIG_HEADER).and_then(|s| Signature::from_str(s).ok()),
+        header(req, POLY_TS_HEADER),
+        header(req, POLY_NONCE_HEADER).and_then(|n| U256::from_str(n).ok()),
+    ) else {
+        return false;
+    };
+    let (payload, domain) = clob_auth_payload(address, timestamp.to_owned(), nonce);
+    signature
+        .recover_address_from_prehash(&payload.eip712_signing_hash(&domain))
+        .is_ok_and(|a| a == address)
+}
+
+fn check_l2(state: &MockState, req: &Parts, body: &str) -> bool {
+    let (Some(_), Some(signature), Some(timestamp), Some(api_key), Some(passphrase)) = (
+        header(req, POLY_ADDR_HEADER),
+        header(req, POLY_SIG_HEADER),
+        header(req, POLY_TS_HEADER).and_then(|t| t.parse::<u64>().ok()),
+        header(req, POLY_API_KEY_HEADER),
+        header(req, POLY_PASS_HEADER),
+    ) else {
+        return false;
+    };
+    if api_key != state.api_key || passphrase != state.passphrase {
+        return false;
+    }
+    let body = (!body.is_empty()).then_some(body);
+    build_hmac_signature_raw(
+        &state.secret,
+        timestamp,
+        req.method.as_str(),
+        req.uri.path(),
+        body,
+    )
+    .is_ok_and(|expected| expected == signature)
+}
+
+fn token_param(req: &Parts) -> Option<String> {
+    query(req).remove("token_id")
+}
+
+fn paginate(items: Vec<Value>, cursor: Option<&String>, page_size: usize) -> Value {
+    let offset = cursor
+        .and_then(|c| STANDARD.decode(c).ok())
+        .and_then(|c| String::from_utf8(c).ok())
+        .and_then(|c| c.parse::<usize>().ok())
+        .unwrap_or(0);
+    let page: Vec<_> = items.iter().skip(offset).take(page_size).cloned().collect();
+    let next = offset + page.len();
+    let next_cursor = if next >= items.len() {
+        END_CURSOR.to_owned()
+    } else {
+        STANDARD.encode(next.to_string())
+    };
+    json!({
+        "limit": page_size,
+        "count": page.len(),
+        "next_cursor": next_cursor,
+        "data": page,
+    })
+}
+
+fn amount(v: &Value) -> Option<Decimal> {
+    Decimal::from_str(v.as_str()?)
+        .ok()
+        .map(|d| d / Decimal::from(1_000_000))
+}
+
+fn post_order(state: &mut MockState, body: &Value) -> MockResponse {
+    let order = &body["order"];
+    if body["owner"].as_str() != Some(state.api_key.as_str()) {
+        return error(
+            StatusCode::BAD_REQUEST,
+            "the order owner has to be the owner of the API KEY",
+        );
+    }
+    let (Some(token_id), Some(maker), Some(taker), Ok(side)) = (
+        order["tokenId"].as_str(),
+        amount(&order["makerAmount"]),
+        amount(&order["takerAmount"]),
+        serde_json::from_value::<Side>(order["side"].clone()),
+    ) else {
+        return error(StatusCode::BAD_REQUEST, "Invalid order payload");
+    };
+    if maker.is_zero() || taker.is_zero() {
+        return error(StatusCode::BAD_REQUEST, "invalid order amounts");
+    }
+    let (price, size) = match side {
+        Side::BUY => (maker / taker, taker),
+        Side::SELL => (taker / maker, maker),
+    };
+    if let Some(tick) = state.tick_sizes.get(token_id) {
+        if !(price % tick).is_zero() {
+            return error(
+                StatusCode::BAD_REQUEST,
+                &format!("order {price} is invalid. Price breaks minimum tick size rule: {tick}"),
+            );
+        }
+    }
+
+    if body["postOnly"] == true {
+        let opposite = match side {
+            Side::BUY => "asks",
+            Side::SELL => "bids",
+        };
+        let crosses = state
+            .books
+            .get(token_id)
+            .and_then(|b| b[opposite].as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|l| Decimal::from_str(l["price"].as_str()?).ok())
+            .any(|p| match side {
+                Side::BUY => p <= price,
+                Side::SELL => p >= price,
+            });
+        if crosses {
+            return error(
+                StatusCode::BAD_REQUEST,
+                "invalid post-only order: order crosses book",
+            );
+        }
+    }
+
+    state.next_order_id += 1;
+    let id = format!("0x{:064x}", state.next_order_id);
+    let market = state
+        .books
+        .get(token_id)
+        .and_then(|b| b["market"].as_str())
+        .unwrap_or_default()
+        .to_owned();
+    state.orders.push(json!({
+        "id": id,
+        "status": "LIVE",
+        "market": market,
+        "asset_id": token_id,
+        "side": side.as_str(),
+        "price": price.normalize().to_string(),
+        "original_size": size.normalize().to_string(),
+        "size_matched": "0",
+        "outcome": "",
+        "owner": state.api_key,
+        "maker_address": order["maker"],
+        "expiration": order["expiration"],
+        "type": body["orderType"],
+        "created_at": get_current_unix_time_secs(),
+        "associate_trades": [],
+    }));
+
+    reply(
+        StatusCode::OK,
+        json!({ "success": true, "errorMsg": "", "orderID": id, "status": "live", "orderHashes": [] }),
+    )
+}
+
+fn cancel(state: &mut MockState, matches: impl Fn(&Value) -> bool) -> MockResponse {
+    let mut canceled = Vec::new();
+    for order in state.orders.iter_mut() {
+        if order["status"] == "LIVE" && matches(order) {
+            order["status"] = json!("CANCELED");
+            canceled.push(order["id"].clone());
+        }
+    }
+    reply(
+        StatusCode::OK,
+        json!({ "canceled": canceled, "not_canceled": {} }),
+    )
+}
+
+async fn handle(
+    state: Arc<Mutex<MockState>>,
+    req: Request<Incoming>,
+) -> Result<MockResponse, Infallible> {
+    let (req, body) = req.into_parts();
+    let body = body
+        .collect()
+        .await
+        .map(|b| b.to_bytes())
+        .unwrap_or_default();
+    let body = String::from_utf8_lossy(&body).into_owned();
+    let json_body: Value = serde_json::from_str(&body).unwrap_or(Value::Null);
+    let params = query(&req);
+
+    let mut state = state.lock().expect("Mock state lock poisoned");
+    let path = req.uri.path().to_owned();
+    *state.requests.entry(path.clone()).or_default() += 1;
+
+    let resp = match (req.method.clone(), path.as_str()) {
+        (Method::GET, "/") => reply(StatusCode::OK, json!("OK")),
+        (Method::GET, "/time") => reply(
+            StatusCode::OK,
+            json!(get_current_unix_time_secs().saturating_add_signed(state.clock_offset)),
+        ),
+
+        (Method::POST, "/auth/api-key") | (Method::GET, "/auth/derive-api-key") => {
+            if check_l1(&req) {
+                reply(
+                    StatusCode::OK,
+                    json!({ "apiKey": state.api_key, "secret": state.secret, "passphrase": state.passphrase }),
+                )
+            } else {
+                error(StatusCode::UNAUTHORIZED, "Invalid L1 Request headers")
+            }
+        }
+
+        (Method::GET, "/book") => match token_param(&req).and_then(|t| state.books.get(&t)) {
+            Some(book) => reply(StatusCode::OK, book.clone()),
+            None => error(
+                StatusCode::NOT_FOUND,
+                "No orderbook exists for the requested token id",
+            ),
+        },
+        (Method::POST, "/books") => {
+            let books = json_body
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|p| state.books.get(p["token_id"].as_str()?).cloned())
+                .collect::<Vec<_>>();
+            reply(StatusCode::OK, json!(books))
+        }
+        (Method::GET, "/tick-size") => {
+            match token_param(&req).and_then(|t| state.tick_sizes.get(&t)) {
+                Some(tick) => reply(
+                    StatusCode::OK,
+                    json!({ "minimum_tick_size": tick.to_string().parse::<f64>().unwrap_or_default() }),
+                ),
+                None => error(StatusCode::NOT_FOUND, "market not found"),
+            }
+        }
+        (Method::GET, "/neg-risk") => {
+            let neg_risk = token_param(&req)
+                .and_then(|t| state.neg_risk.get(&t).copied())
+                .unwrap_or(false);
+            reply(StatusCode::OK, json!({ "neg_risk": neg_risk }))
+        }
+        (Method::GET, "/fee-rate") => {
+            let fee = token_param(&req)
+                .and_then(|t| state.fee_rates.get(&t).copied())
+                .unwrap_or(0);
+            reply(StatusCode::OK, json!({ "base_fee": fee }))
+        }
+
+        (Method::GET, "/markets") => {
+            let mut markets: Vec<_> = state.markets.values().cloned().collect();
+            markets.sort_by(|a, b| a["condition_id"].as_str().cmp(&b["condition_id"].as_str()));
+            reply(
+                StatusCode::OK,
+                paginate(markets, params.get("next_cursor"), state.page_size),
+            )
+        }
+        (Method::GET, p) if p.starts_with("/markets/") => {
+            match state.markets.get(&p["/markets/".len()..]) {
+                Some(market) => reply(StatusCode::OK, market.clone()),
+                None => error(StatusCode::NOT_FOUND, "market not found"),
+            }
+        }
+
+        _ if !check_l2(&state, &req, &body) => {
+            error(StatusCode::UNAUTHORIZED, "Unauthorized/Invalid api key")
+        }
+
+        (Method::POST, "/order") => post_order(&mut state, &json_body),
+        (Method::DELETE, "/order") => {
+            let id = json_body["orderID"].clone();
+            cancel(&mut state, |o| o["id"] == id)
+        }
+        (Method::DELETE, "/orders") => {
+            let ids = json_body.as_array().cloned().unwrap_or_default();
+            cancel(&mut state, |o| ids.contains(&o["id"]))
+        }
+        (Method::DELETE, "/cancel-all") => cancel(&mut state, |_| true),
+        (Method::DELETE, "/cancel-market-orders") => {
+            let market = json_body["market"].as_str().unwrap_or_default().to_owned();
+            let asset = json_body["asset_id"]
+                .as_str()
+                .unwrap_or_default()
+                .to_owned();
+            cancel(&mut state, |o| {
+                (market.is_empty() || o["market"] == market.as_str())
+                    && (asset.is_empty() || o["asset_id"] == asset.as_str())
+            })
+        }
+        (Method::GET, "/data/orders") => {
+            let orders = state
+                .orders
+                .iter()
+                .filter(|o| o["status"] == "LIVE")
+                .filter(|o| {
+                    ["id", "market", "asset_id"].iter().all(|k| {
+                        params
+                            .get(*k)
+                            .is_none_or(|v| o[*k].as_str() == Some(v.as_str()))
+                    })
+                })
+                .cloned()
+                .collect();
+            reply(
+                StatusCode::OK,
+                paginate(orders, params.get("next_cursor"), state.page_size),
+            )
+        }
+        (Method::GET, p) if p.starts_with("/data/order/") => {
+            let id = &p["/data/order/".len()..];
+            let order = state.orders.iter().find(|o| o["id"] == id).cloned();
+            reply(StatusCode::OK, order.unwrap_or(Value::Null))
+        }
+        (Method::GET, "/data/trades") => {
+            let trades = state.trades.clone();
+            reply(
+                StatusCode::OK,
+                paginate(trades, params.get("next_cursor"), state.page_size),
+            )
+        }
+
+        _ => error(StatusCode::NOT_FOUND, "Not found"),
+    };
+    Ok(resp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ArbKind, ArbScanner, ArbScannerConfig, ClobClient, Expiry, ExtraOrderArgs, MarketOrderArgs,
+        MetadataCacheConfig, OrderArgs, OrderValidationError, ReplaceMode, TimeInForce,
+        GTD_EXPIRATION_BUFFER_SECS,
+    };
+    use std::time::Duration;
+
+    const TEST_KEY: &str = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+    fn dec(s: &str) -> Decimal {
+        Decimal::from_str(s).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_mock_market_data() {
+        let mock = MockClob::start().await.unwrap();
+        mock.set_book(
+            "1",
+            "0xm",
+            &[(dec("0.4"), dec("10"))],
+            &[(dec("0.6"), dec("5"))],
+        );
+        mock.set_tick_size("1", dec("0.01"));
+        mock.set_fee_rate_bps("1", 1000);
+
+        let client = ClobClient::new(&mock.url());
+        let book = client.get_order_book("1").await.unwrap();
+        assert_eq!(book.asks[0].price, dec("0.6"));
+        assert_eq!(client.get_tick_size("1").await.unwrap(), dec("0.01"));
+        assert!(!client.get_neg_risk("1").await.unwrap());
+        assert_eq!(client.get_fee_rate_bps("1").await.unwrap(), 1000);
+        assert_eq!(
+            client.get_order_books(&["1".into()]).await.unwrap().len(),
+            1
+        );
+        assert!(client.get_order_book("2").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_mock_auth_and_order_flow() {
+        let mock = MockClob::start().await.unwrap();
+        mock.set_book("1", "0xm", &[], &[]);
+        mock.set_tick_size("1", dec("0.01"));
+        mock.set_page_size(2);
+
+        let mut client = ClobClient::with_l1_headers(&mock.url(), TEST_KEY, 137);
+        let creds = client.create_or_derive_api_key(None).await.unwrap();
+        assert_eq!(creds.api_key, mock.api_creds().api_key);
+        client.set_api_creds(creds);
+
+        for i in 1..=3 {
+            let args = OrderArgs::new("1", dec("0.5"), Decimal::from(i * 10), Side::BUY);
+            let resp = client.create_and_post_order(&args).await.unwrap();
+            assert_eq!(resp["success"], true);
+        }
+
+        let orders = client.get_orders(None, None).await.unwrap();
+        assert_eq!(orders.len(), 3);
+        assert_eq!(orders[2].original_size, Some(dec("30")));
+
+        let resp = client.cancel(&orders[0].id).await.unwrap();
+        assert_eq!(resp["canceled"][0], orders[0].id.as_str());
+        assert_eq!(client.get_orders(None, None).await.unwrap().len(), 2);
+
+        client.cancel_all().await.unwrap();
+        assert!(client.get_orders(None, None).await.unwrap().is_empty());
+        assert_eq!(mock.orders().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_replace_order() {
+        let mock = MockClob::start().await.unwrap();
+        mock.set_book("1", "0xm", &[], &[]);
+        mock.set_tick_size("1", dec("0.01"));
+        let client = ClobClient::with_l2_headers(&mock.url(), TEST_KEY, 137, mock.api_creds());
+
+        let args = OrderArgs::new("1", dec("0.5"), dec("10"), Side::BUY);
+        let old = client.create_and_post_order(&args).await.unwrap();
+        let old_id = old["orderID"].as_str().unwrap();
+
+        let args = OrderArgs::new("1", dec("0.51"), dec("10"), Side::BUY);
+        let outcome = client
+            .replace_order(old_id, &args, ReplaceMode::CancelFirst)
+            .await
+            .unwrap();
+        assert!(outcome.is_complete());
+        let orders = client.get_orders(None, None).await.unwrap();
+        assert_eq!(orders.len(), 1);
+        assert_eq!(Some(orders[0].id.as_str()), outcome.new_order_id());
+
+        // The old order is gone, so nothing is posted in its place.
+        let outcome = client
+            .replace_order(old_id, &args, ReplaceMode::CancelFirst)
+            .await
+            .unwrap();
+        assert!(!outcome.cancelled && outcome.posted.is_none());
+        assert_eq!(client.get_orders(None, None).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_tick_size_cache_invalidated_on_rejection() {
+        let mock = MockClob::start().await.unwrap();
+        mock.set_book("1", "0xm", &[], &[]);
+        mock.set_tick_size("1", dec("0.01"));
+        let client = ClobClient::with_l2_headers(&mock.url(), TEST_KEY, 137, mock.api_creds());
+        client.prewarm_metadata(&["1".into()]).await.unwrap();
+
+        mock.set_tick_size("1", dec("0.1"));
+        assert_eq!(client.get_tick_size("1").await.unwrap(), dec("0.01"));
+        let args = OrderArgs::new("1", dec("0.55"), dec("10"), Side::BUY);
+        let err = client.create_and_post_order(&args).await.unwrap_err();
+        assert!(err.to_string().contains(crate::TICK_SIZE_REJECTION));
+        assert_eq!(client.get_tick_size("1").await.unwrap(), dec("0.1"));
+    }
+
+    #[tokio::test]
+    async fn test_order_validation() {
+        let mock = MockClob::start().await.unwrap();
+        mock.set_book("1", "0xm", &[], &[]);
+        mock.set_tick_size("1", dec("0.01"));
+        mock.set_fee_rate_bps("1", 1000);
+        mock.set_minimum_order_size("0xm", dec("15"));
+        let client = ClobClient::with_l2_headers(&mock.url(), TEST_KEY, 137, mock.api_creds());
+
+        let validate = |args: OrderArgs, expiration: u64, fee_rate_bps: u32| {
+            let client = &client;
+            async move {
+                let extras = ExtraOrderArgs {
+                    fee_rate_bps: Some(fee_rate_bps),
+                    ..Default::default()
+                };
+                client
+                    .create_order(&args, Some(expiration), Some(extras), None)
+                    .await
+                    .map(drop)
+                    .map_err(|e| e.downcast::<OrderValidationError>().unwrap())
+            }
+        };
+        let args = |size: &str| OrderArgs::new("1", dec("0.5"), dec(size), Side::BUY);
+
+        assert!(validate(args("20"), 0, 1000).await.is_ok());
+        let order = client
+            .create_order(&args("20"), None, None, None)
+            .await
+            .unwrap();
+        assert_eq!(order.fee_rate_bps, "1000");
+        assert!(matches!(
+            validate(args("10"), 0, 1000).await,
+            Err(OrderValidationError::SizeBelowMinimum { .. })
+        ));
+        assert_eq!(
+            validate(args("20"), 0, 0).await,
+            Err(OrderValidationError::FeeRateMismatch {
+                fee_rate_bps: 0,
+                expected: 1000
+            })
+        );
+
+        // Expirations are checked against the exchange's clock.
+        let expiration = get_current_unix_time_secs() + 600;
+        assert!(validate(args("20"), expiration, 1000).await.is_ok());
+        mock.set_clock_offset(3600);
+        assert!(matches!(
+            validate(args("20"), expiration, 1000).await,
+            Err(OrderValidationError::ExpirationTooSoon { .. })
+        ));
+
+        mock.set_market_status("0xm", true, true);
+        client.invalidate_metadata("1");
+        assert!(matches!(
+            validate(args("20"), 0, 1000).await,
+            Err(OrderValidationError::MarketClosed { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_gtd_time_in_force() {
+        let mock = MockClob::start().await.unwrap();
+        mock.set_book("1", "0xm", &[], &[]);
+        mock.set_tick_size("1", dec("0.01"));
+        let client = ClobClient::with_l2_headers(&mock.url(), TEST_KEY, 137, mock.api_creds());
+
+        let args = OrderArgs::new("1", dec("0.5"), dec("10"), Side::BUY)
+            .with_time_in_force(TimeInForce::GTD(Expiry::After(Duration::from_secs(120))));
+        let before = client.get_server_time().await.unwrap();
+        client.create_and_post_order(&args).await.unwrap();
+
+        let order = &mock.orders()[0];
+        assert_eq!(order["type"], "GTD");
+        let expiration: u64 = order["expiration"].as_str().unwrap().parse().unwrap();
+        let expected = before + 120 + GTD_EXPIRATION_BUFFER_SECS;
+        assert!((expected..expected + 5).contains(&expiration));
+    }
+
+    #[tokio::test]
+    async fn test_post_only() {
+        let mock = MockClob::start().await.unwrap();
+        mock.set_book(
+            "1",
+            "0xm",
+            &[(dec("0.4"), dec("10"))],
+            &[(dec("0.6"), dec("10"))],
+        );
+        mock.set_tick_size("1", dec("0.01"));
+        let mut client = ClobClient::with_l2_headers(&mock.url(), TEST_KEY, 137, mock.api_creds());
+        client.set_metadata_cache_config(MetadataCacheConfig {
+            top_of_book_ttl: Duration::from_secs(3600),
+            ..Default::default()
+        });
+        let bid = |price: &str| {
+            OrderArgs::new("1", dec(price), dec("10"), Side::BUY).with_post_only(true)
+        };
+
+        let err = client
+            .create_order(
+                &bid("0.55").with_time_in_force(TimeInForce::FOK),
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<OrderValidationError>(),
+            Some(&OrderValidationError::PostOnlyNotResting {
+                time_in_force: TimeInForce::FOK
+            })
+        );
+
+        let err = client.create_and_post_order(&bid("0.6")).await.unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<OrderValidationError>(),
+            Some(&OrderValidationError::WouldCross {
+                price: dec("0.6"),
+                best: dec("0.6")
+            })
+        );
+        client.create_and_post_order(&bid("0.55")).await.unwrap();
+
+        // With a stale top of book the client lets the order through and the exchange
+        // refuses it thanks to the postOnly flag.
+        mock.set_book(
+            "1",
+            "0xm",
+            &[(dec("0.4"), dec("10"))],
+            &[(dec("0.5"), dec("10"))],
+        );
+        let err = client
+            .create_and_post_order(&bid("0.55"))
+            .await
+            .unwrap_err();
+        assert!(err.downcast_ref::<OrderValidationError>().is_none());
+        assert!(err.to_string().contains("order crosses book"));
+    }
+
+    #[tokio::test]
+    async fn test_market_order_slippage() {
+        let mock = MockClob::start().await.unwrap();
+        mock.set_book(
+            "1",
+            "0xm",
+            &[],
+            &[(dec("0.5"), dec("10")), (dec("0.6"), dec("100"))],
+        );
+        mock.set_tick_size("1", dec("0.01"));
+        let client = ClobClient::with_l2_headers(&mock.url(), TEST_KEY, 137, mock.api_creds());
+
+        let args = MarketOrderArgs::new("1", dec("11"));
+        let quote = client.quote_market_order(&args).await.unwrap();
+        assert_eq!(quote.worst_price, dec("0.6"));
+        assert_eq!(quote.average_price, dec("0.55"));
+
+        let err = client
+            .create_market_order(&args.with_max_slippage(dec("0.05")), None, None)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<OrderValidationError>(),
+            Some(&OrderValidationError::SlippageExceeded {
+                worst_price: dec("0.6"),
+                limit: dec("0.55"),
+            })
+        );
+
+        let args = MarketOrderArgs::new("1", dec("5")).with_worst_price(dec("0.5"));
+        let (order, quote) = client
+            .create_market_order_with_quote(&args, None, None)
+            .await
+            .unwrap();
+        assert_eq!(order.taker_amount, "10000000");
+        assert_eq!(quote.size, dec("10"));
+    }
+
+    #[tokio::test]
+    async fn test_synthetic_books() {
+        let mock = MockClob::start().await.unwrap();
+        mock.set_book(
+            "yes",
+            "0xm",
+            &[(dec("0.45"), dec("10"))],
+            &[(dec("0.6"), dec("10"))],
+        );
+        mock.set_book(
+            "no",
+            "0xm",
+            &[(dec("0.42"), dec("5"))],
+            &[(dec("0.5"), dec("20"))],
+        );
+        let client = ClobClient::new(&mock.url());
+        let market = client.get_market("0xm").await.unwrap();
+
+        // Each synthetic book is built from its own token's book and the complement's.
+        let [yes, no] = client.get_synthetic_books(&market.tokens).await.unwrap();
+        assert_eq!((yes.token_id.as_str(), no.token_id.as_str()), ("yes", "no"));
+        assert_eq!(yes.combined.best_ask(), Some(dec("0.58")));
+        assert_eq!(no.combined.best_bid(), Some(dec("0.42")));
+    }
+
+    #[tokio::test]
+    async fn test_arb_scanner() {
+        let mock = MockClob::start().await.unwrap();
+        mock.set_book("yes", "0xm", &[], &[(dec("0.45"), dec("10"))]);
+        mock.set_book("no", "0xm", &[], &[(dec("0.5"), dec("20"))]);
+        mock.set_fee_rate_bps("yes", 1000);
+        for (market, ask, size) in [
+            ("0xa", "0.3", "5"),
+            ("0xb", "0.3", "8"),
+            ("0xc", "0.35", "10"),
+        ] {
+            let yes = format!("{market}-yes");
+            let no = format!("{market}-no");
+            mock.set_book(&yes, market, &[], &[(dec(ask), dec(size))]);
+            mock.set_book(&no, market, &[], &[(dec("0.75"), dec("10"))]);
+            mock.set_neg_risk_market(market, "0xevent");
+        }
+        mock.set_page_size(2);
+        let client = ClobClient::new(&mock.url());
+        let mut markets = Vec::new();
+        for id in ["0xm", "0xa", "0xb", "0xc"] {
+            markets.push(client.get_market(id).await.unwrap());
+        }
+
+        let found = ArbScanner::default().scan(&client, &markets).await.unwrap();
+        assert_eq!(found.len(), 2);
+        // 1 - 0.45 - 0.5 - 0.045 in fees on the YES leg.
+        assert_eq!(found[0].market, "0xm");
+        assert_eq!(found[0].kind, ArbKind::BuyAll);
+        assert_eq!(found[0].edge, dec("0.005"));
+        assert_eq!(found[1].market, "0xevent");
+        assert_eq!(found[1].legs.len(), 3);
+        assert_eq!(found[1].total_price, dec("0.95"));
+    }
+
+    #[tokio::test]
+    async fn test_arb_scanner_skips_incomplete_events() {
+        let mock = MockClob::start().await.unwrap();
+        for (market, ask) in [("0xa", "0.3"), ("0xb", "0.3"), ("0xc", "0.35")] {
+            mock.set_book(
+                &format!("{market}-yes"),
+                market,
+                &[],
+                &[(dec(ask), dec("10"))],
+            );
+            mock.set_book(&format!("{market}-no"), market, &[], &[]);
+            mock.set_neg_risk_market(market, "0xevent");
+        }
+        let client = ClobClient::new(&mock.url());
+        let mut markets = Vec::new();
+        for id in ["0xa", "0xb"] {
+            markets.push(client.get_market(id).await.unwrap());
+        }
+        let scanner = ArbScanner::new(ArbScannerConfig {
+            fee_rate_bps: Some(0),
+            ..Default::default()
+        });
+
+        // Two YES asks summing to 0.6 look cheap only because 0xc is left out.
+        assert!(scanner.scan(&client, &markets).await.unwrap().is_empty());
+        let pages = mock.request_count("/markets");
+
+        // The event's listing is cached, so the next scan does not walk the pages again.
+        markets.push(client.get_market("0xc").await.unwrap());
+        let found = scanner.scan(&client, &markets).await.unwrap();
+        assert_eq!(mock.request_count("/markets"), pages);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].kind, ArbKind::BuyAll);
+        assert_eq!(found[0].total_price, dec("0.95"));
+    }
+
+    #[tokio::test]
+    async fn test_mock_rejects_bad_credentials() {
+        let mock = MockClob::start().await.unwrap();
+        let mut creds = mock.api_creds();
+        creds.secret = "AQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQE=".into();
+        let client = ClobClient::with_l2_headers(&mock.url(), TEST_KEY, 137, creds);
+
+        let err = client.cancel_all().await.unwrap();
+        assert_eq!(err["error"], "Unauthorized/Invalid api key");
+    }
+}
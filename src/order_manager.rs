@@ -0,0 +1,480 @@
+//! Local view of the lifecycle of every order we place.
+//!
+//! [`OrderManager`] is fed from three sources: `post_order` responses, `get_order`
+//! polling and user-channel events. Each source is idempotent, so the same fill
+//! reported by several of them is only counted once.
+
+use crate::utils::get_current_unix_time_secs;
+use crate::{
+    ClientResult, OpenOrder, OrderEventType, OrderType, OrderVenue, Side, SignedOrderRequest,
+    Trade, UserEvent, UserOrderEvent, Value,
+};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Mutex, MutexGuard};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderState {
+    /// Sent, or accepted but delayed, and not yet live in the book.
+    Pending,
+    Live,
+    PartiallyFilled,
+    Filled,
+    Cancelled,
+    /// A GTD order cancelled by the exchange at its expiration.
+    Expired,
+    Rejected,
+}
+
+impl OrderState {
+    /// Whether the order can still trade.
+    pub fn is_open(&self) -> bool {
+        matches!(
+            self,
+            OrderState::Pending | OrderState::Live | OrderState::PartiallyFilled
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ManagedOrder {
+    /// Assigned when the order is recorded, before the exchange returns an ID.
+    pub local_id: u64,
+    pub order_id: Option<String>,
+    pub order: SignedOrderRequest,
+    pub order_type: OrderType,
+    pub token_id: String,
+    pub side: Side,
+    pub price: Decimal,
+    pub size: Decimal,
+    pub size_matched: Decimal,
+    pub state: OrderState,
+    /// Rejection reason from the exchange.
+    pub error: Option<String>,
+    expiration: u64,
+    /// Fills per trade ID, as reported by trade events.
+    trade_fills: HashMap<String, Decimal>,
+}
+
+impl ManagedOrder {
+    pub fn remaining(&self) -> Decimal {
+        (self.size - self.size_matched).max(Decimal::ZERO)
+    }
+
+    /// Raises `size_matched` to `matched` and moves the state forward accordingly.
+    fn update_matched(&mut self, matched: Decimal) {
+        self.size_matched = self.size_matched.max(matched);
+        if self.size_matched >= self.size {
+            self.state = OrderState::Filled;
+        } else if self.state.is_open() && self.size_matched > Decimal::ZERO {
+            self.state = OrderState::PartiallyFilled;
+        }
+    }
+
+    fn mark_live(&mut self) {
+        if self.state == OrderState::Pending {
+            self.state = OrderState::Live;
+        }
+        self.update_matched(self.size_matched);
+    }
+
+    fn mark_cancelled(&mut self) {
+        if self.state.is_open() {
+            let expired = self.order_type == OrderType::GTD
+                && self.expiration != 0
+                && self.expiration <= get_current_unix_time_secs();
+            self.state = if expired {
+                OrderState::Expired
+            } else {
+                OrderState::Cancelled
+            };
+        }
+    }
+
+    fn apply_status(&mut self, status: &str) {
+        match status {
+            "LIVE" => self.mark_live(),
+            "MATCHED" => self.update_matched(self.size),
+            "DELAYED" => {}
+            "INVALID" => self.state = OrderState::Rejected,
+            s if s.starts_with("CANCELED") || s == "UNMATCHED" => self.mark_cancelled(),
+            s => tracing::debug!(status = s, "unknown order status"),
+        }
+    }
+}
+
+#[derive(Default)]
+struct ManagerState {
+    next_id: u64,
+    orders: Vec<ManagedOrder>,
+}
+
+impl ManagerState {
+    fn by_local_id(&mut self, local_id: u64) -> Option<&mut ManagedOrder> {
+        self.orders.iter_mut().find(|o| o.local_id == local_id)
+    }
+
+    fn by_order_id(&mut self, order_id: &str) -> Option<&mut ManagedOrder> {
+        self.orders
+            .iter_mut()
+            .find(|o| o.order_id.as_deref() == Some(order_id))
+    }
+}
+
+/// Tracks the state of every order we place. Safe to share between tasks.
+#[derive(Default)]
+pub struct OrderManager {
+    state: Mutex<ManagerState>,
+}
+
+impl OrderManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records an order about to be posted and returns its local ID.
+    pub fn insert_pending(
+        &self,
+        order: SignedOrderRequest,
+        order_type: OrderType,
+    ) -> ClientResult<u64> {
+        let (side, price, size) = order.terms()?;
+        let expiration = order.expiration.parse().unwrap_or_default();
+        let mut state = self.state();
+        state.next_id += 1;
+        let local_id = state.next_id;
+        state.orders.push(ManagedOrder {
+            local_id,
+            order_id: None,
+            token_id: order.token_id.clone(),
+            order,
+            order_type,
+            side,
+            price,
+            size,
+            size_matched: Decimal::ZERO,
+            state: OrderState::Pending,
+            error: None,
+            expiration,
+            trade_fills: HashMap::new(),
+        });
+        Ok(local_id)
+    }
+
+    /// Applies the result of posting the order recorded as `local_id`.
+    pub fn on_post_response(&self, local_id: u64, response: &ClientResult<Value>) {
+        let mut state = self.state();
+        let Some(order) = state.by_local_id(local_id) else {
+            return;
+        };
+        let resp = match response {
+            Ok(resp) => resp,
+            Err(e) => {
+                order.state = OrderState::Rejected;
+                order.error = Some(e.to_string());
+                return;
+            }
+        };
+        let order_id = resp["orderID"].as_str().filter(|id| !id.is_empty());
+        if resp["success"] == false || order_id.is_none() {
+            order.state = OrderState::Rejected;
+            order.error = resp["errorMsg"].as_str().map(str::to_owned);
+            return;
+        }
+        order.order_id = order_id.map(str::to_owned);
+
+        // Amounts filled on arrival: shares are the taking side of a buy and the making side of a sell.
+        let filled = match order.side {
+            Side::BUY => &resp["takingAmount"],
+            Side::SELL => &resp["makingAmount"],
+        }
+        .as_str()
+        .and_then(|a| Decimal::from_str(a).ok());
+        match resp["status"].as_str().unwrap_or_default() {
+            "live" => {
+                order.mark_live();
+                if let Some(filled) = filled {
+                    order.update_matched(filled);
+                }
+            }
+            "matched" => {
+                order.mark_live();
+                order.update_matched(filled.filter(|f| !f.is_zero()).unwrap_or(order.size));
+                // Whatever an immediate-or-cancel order did not fill on arrival is gone.
+                if matches!(order.order_type, OrderType::FOK | OrderType::FAK) {
+                    order.mark_cancelled();
+                }
+            }
+            "unmatched" => order.mark_cancelled(),
+            _ => {}
+        }
+    }
+
+    /// Records `order`, posts it through `venue` and applies the response. The order
+    /// stays tracked as rejected if posting fails.
+    pub async fn post_order<V: OrderVenue + ?Sized>(
+        &self,
+        venue: &V,
+        order: SignedOrderRequest,
+        order_type: OrderType,
+    ) -> ClientResult<u64> {
+        let local_id = self.insert_pending(order.clone(), order_type)?;
+        let response = venue.post_order(order, order_type).await;
+        self.on_post_response(local_id, &response);
+        response.map(|_| local_id)
+    }
+
+    /// Applies an order fetched with `get_order`.
+    pub fn on_open_order(&self, open: &OpenOrder) {
+        let mut state = self.state();
+        let Some(order) = state.by_order_id(&open.id) else {
+            return;
+        };
+        if let Some(matched) = open.size_matched {
+            order.update_matched(matched);
+        }
+        order.apply_status(&open.status);
+    }
+
+    /// Polls `get_order` for every open order that has an exchange ID.
+    pub async fn refresh<V: OrderVenue + ?Sized>(&self, venue: &V) -> ClientResult<()> {
+        let ids: Vec<_> = self
+            .state()
+            .orders
+            .iter()
+            .filter(|o| o.state.is_open())
+            .filter_map(|o| o.order_id.clone())
+            .collect();
+        for id in ids {
+            if let Some(open) = venue.get_order(&id).await? {
+                self.on_open_order(&open);
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies a message from the user channel.
+    pub fn on_user_event(&self, event: &UserEvent) {
+        match event {
+            UserEvent::Order(event) => self.on_order_event(event),
+            UserEvent::Trade(trade) => self.on_trade(trade),
+        }
+    }
+
+    fn on_order_event(&self, event: &UserOrderEvent) {
+        let mut state = self.state();
+        let Some(order) = state.by_order_id(&event.id) else {
+            return;
+        };
+        match event.event_type {
+            OrderEventType::Placement => order.mark_live(),
+            OrderEventType::Update => order.update_matched(event.size_matched),
+            OrderEventType::Cancellation => {
+                order.update_matched(event.size_matched);
+                order.mark_cancelled();
+            }
+        }
+    }
+
+    fn on_trade(&self, trade: &Trade) {
+        if trade.status == "FAILED" {
+            return;
+        }
+        let fills = std::iter::once((trade.taker_order_id.as_str(), trade.size)).chain(
+            trade
+                .maker_orders
+                .iter()
+                .map(|m| (m.order_id.as_str(), m.matched_amount)),
+        );
+        let mut state = self.state();
+        for (order_id, size) in fills {
+            let Some(order) = state.by_order_id(order_id) else {
+                continue;
+            };
+            order.trade_fills.insert(trade.id.clone(), size);
+            let matched = order.trade_fills.values().sum();
+            order.update_matched(matched);
+        }
+    }
+
+    pub fn get(&self, local_id: u64) -> Option<ManagedOrder> {
+        self.state().by_local_id(local_id).cloned()
+    }
+
+    pub fn get_by_order_id(&self, order_id: &str) -> Option<ManagedOrder> {
+        self.state().by_order_id(order_id).cloned()
+    }
+
+    /// Orders for `token_id` that can still trade.
+    pub fn open_orders(&self, token_id: &str) -> Vec<ManagedOrder> {
+        self.state()
+            .orders
+            .iter()
+            .filter(|o| o.token_id == token_id && o.state.is_open())
+            .cloned()
+            .collect()
+    }
+
+    /// Orders, across all tokens, that can still trade.
+    pub fn all_open_orders(&self) -> Vec<ManagedOrder> {
+        self.state()
+            .orders
+            .iter()
+            .filter(|o| o.state.is_open())
+            .cloned()
+            .collect()
+    }
+
+    /// Number of orders, across all tokens, that can still trade.
+    pub fn open_order_count(&self) -> usize {
+        self.state()
+            .orders
+            .iter()
+            .filter(|o| o.state.is_open())
+            .count()
+    }
+
+    /// Unfilled size of our open orders for `token_id` on `side` at `price`.
+    pub fn resting_size(&self, token_id: &str, side: Side, price: Decimal) -> Decimal {
+        self.state()
+            .orders
+            .iter()
+            .filter(|o| {
+                o.token_id == token_id && o.side == side && o.price == price && o.state.is_open()
+            })
+            .map(ManagedOrder::remaining)
+            .sum()
+    }
+
+    /// Forgets orders that can no longer trade.
+    pub fn remove_closed(&self) {
+        self.state().orders.retain(|o| o.state.is_open());
+    }
+
+    fn state(&self) -> MutexGuard<'_, ManagerState> {
+        self.state.lock().expect("Order manager lock poisoned")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{OrderBookSummary, PaperExchange};
+
+    fn order(side: Side, maker_amount: u64, taker_amount: u64) -> SignedOrderRequest {
+        SignedOrderRequest {
+            salt: 1,
+            maker: "0xmaker".into(),
+            signer: "0xmaker".into(),
+            taker: "0x0000000000000000000000000000000000000000".into(),
+            token_id: "1".into(),
+            maker_amount: maker_amount.to_string(),
+            taker_amount: taker_amount.to_string(),
+            expiration: "0".into(),
+            nonce: "0".into(),
+            fee_rate_bps: "0".into(),
+            side: side.as_str().into(),
+            signature_type: 0,
+            signature: "0x".into(),
+            post_only: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_order_manager_tracks_paper_fills() {
+        let paper = PaperExchange::new();
+        paper.seed_book(&OrderBookSummary {
+            market: "0xmarket".into(),
+            asset_id: "1".into(),
+            hash: String::new(),
+            timestamp: 0,
+            bids: vec![],
+            asks: vec![],
+        });
+        let manager = OrderManager::new();
+
+        // Buy 20 at 0.5, then two sells of 5 and 15 against it.
+        let bid = manager
+            .post_order(
+                &paper,
+                order(Side::BUY, 10_000_000, 20_000_000),
+                OrderType::GTC,
+            )
+            .await
+            .unwrap();
+        assert_eq!(manager.get(bid).unwrap().state, OrderState::Live);
+        assert_eq!(
+            manager.resting_size("1", Side::BUY, Decimal::new(5, 1)),
+            Decimal::from(20)
+        );
+
+        let sell = manager
+            .post_order(
+                &paper,
+                order(Side::SELL, 5_000_000, 2_500_000),
+                OrderType::GTC,
+            )
+            .await
+            .unwrap();
+        assert_eq!(manager.get(sell).unwrap().state, OrderState::Filled);
+        manager.refresh(&paper).await.unwrap();
+        assert_eq!(manager.get(bid).unwrap().state, OrderState::PartiallyFilled);
+        assert_eq!(
+            manager.resting_size("1", Side::BUY, Decimal::new(5, 1)),
+            Decimal::from(15)
+        );
+
+        paper
+            .post_order(order(Side::SELL, 15_000_000, 7_500_000), OrderType::GTC)
+            .await
+            .unwrap();
+        manager.refresh(&paper).await.unwrap();
+        assert_eq!(manager.get(bid).unwrap().state, OrderState::Filled);
+        assert!(manager.open_orders("1").is_empty());
+
+        let rejected = manager
+            .post_order(&paper, order(Side::BUY, 1, 0), OrderType::GTC)
+            .await;
+        assert!(rejected.is_err());
+    }
+
+    #[test]
+    fn test_order_manager_user_events() {
+        let manager = OrderManager::new();
+        let local_id = manager
+            .insert_pending(order(Side::SELL, 10_000_000, 6_000_000), OrderType::GTC)
+            .unwrap();
+        manager.on_post_response(
+            local_id,
+            &Ok(serde_json::json!({"success": true, "orderID": "0xabc", "status": "live"})),
+        );
+
+        let trade: UserEvent = serde_json::from_str(
+            r#"{"event_type": "trade", "type": "TRADE", "id": "t1", "taker_order_id": "0xother",
+                "asset_id": "1", "market": "0xm", "side": "BUY", "size": "4", "price": "0.6",
+                "status": "MATCHED", "matchtime": "1700000000",
+                "maker_orders": [{"order_id": "0xabc", "matched_amount": "4", "price": "0.6"}]}"#,
+        )
+        .unwrap();
+        manager.on_user_event(&trade);
+        // The same trade confirmed later is not counted twice.
+        manager.on_user_event(&trade);
+        let tracked = manager.get(local_id).unwrap();
+        assert_eq!(tracked.state, OrderState::PartiallyFilled);
+        assert_eq!(tracked.size_matched, Decimal::from(4));
+
+        let cancel: UserEvent = serde_json::from_str(
+            r#"{"event_type": "order", "type": "CANCELLATION", "id": "0xabc", "asset_id": "1",
+                "market": "0xm", "side": "SELL", "price": "0.6", "original_size": "10",
+                "size_matched": "4", "timestamp": "1700000001"}"#,
+        )
+        .unwrap();
+        manager.on_user_event(&cancel);
+        assert_eq!(manager.get(local_id).unwrap().state, OrderState::Cancelled);
+        assert_eq!(
+            manager.resting_size("1", Side::SELL, Decimal::new(6, 1)),
+            Decimal::ZERO
+        );
+    }
+}
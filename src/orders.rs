@@ -5,7 +5,9 @@ use anyhow::{Context, Result};
 use rand::thread_rng;
 use rand::Rng;
 use rust_decimal::Decimal;
-use rust_decimal::RoundingStrategy::{AwayFromZero, MidpointTowardZero, ToZero};
+use rust_decimal::RoundingStrategy::{
+    AwayFromZero, MidpointTowardZero, ToNegativeInfinity, ToPositiveInfinity, ToZero,
+};
 
 use serde::Serialize;
 
@@ -13,13 +15,13 @@ use crate::config::get_contract_config;
 use crate::eth_utils::sign_order_message;
 use crate::eth_utils::Order;
 use crate::utils::get_current_unix_time_secs;
+use crate::validation::OrderValidationError;
 use crate::{
-    CreateOrderOptions, EthSigner, ExtraOrderArgs, MarketOrderArgs, OrderArgs, OrderSummary, Side,
+    CreateOrderOptions, EthSigner, ExtraOrderArgs, MarketOrderArgs, MarketOrderQuote, OrderArgs,
+    OrderSummary, Side,
 };
 
-use std::collections::HashMap;
 use std::str::FromStr;
-use std::sync::LazyLock;
 
 /// Signature type for different wallet configurations
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -38,7 +40,9 @@ pub struct OrderBuilder {
     funder: Address,
 }
 
+#[derive(Clone, Copy)]
 pub struct RoundConfig {
+    tick: Decimal,
     price: u32,
     size: u32,
     amount: u32,
@@ -51,7 +55,7 @@ fn generate_seed() -> u64 {
     a as u64
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SignedOrderRequest {
     pub salt: u64,
@@ -67,44 +71,33 @@ pub struct SignedOrderRequest {
     pub side: String,
     pub signature_type: u8,
     pub signature: String,
+    /// Not part of the signed order. Sent as the `postOnly` flag when posting.
+    #[serde(skip)]
+    pub post_only: bool,
 }
 
-static ROUNDING_CONFIG: LazyLock<HashMap<Decimal, RoundConfig>> = LazyLock::new(|| {
-    HashMap::from([
-        (
-            Decimal::from_str("0.1").unwrap(),
-            RoundConfig {
-                price: 1,
-                size: 2,
-                amount: 3,
-            },
-        ),
-        (
-            Decimal::from_str("0.01").unwrap(),
-            RoundConfig {
-                price: 2,
-                size: 2,
-                amount: 4,
-            },
-        ),
-        (
-            Decimal::from_str("0.001").unwrap(),
-            RoundConfig {
-                price: 3,
-                size: 2,
-                amount: 5,
-            },
-        ),
-        (
-            Decimal::from_str("0.0001").unwrap(),
-            RoundConfig {
-                price: 4,
-                size: 2,
-                amount: 6,
-            },
-        ),
-    ])
-});
+impl SignedOrderRequest {
+    /// Side, limit price and size in shares encoded by the signed maker and taker amounts.
+    pub fn terms(&self) -> Result<(Side, Decimal, Decimal)> {
+        let side = match self.side.as_str() {
+            "BUY" => Side::BUY,
+            "SELL" => Side::SELL,
+            s => return Err(anyhow!("Invalid order side {s}")),
+        };
+        let maker = Decimal::from_str(&self.maker_amount).context("Invalid maker amount")?;
+        let taker = Decimal::from_str(&self.taker_amount).context("Invalid taker amount")?;
+        if maker.is_zero() || taker.is_zero() {
+            return Err(anyhow!("Order amounts must be positive"));
+        }
+        let (price, size) = amounts_to_terms(side, maker, taker);
+        Ok((side, price, size))
+    }
+}
+
+/// Amounts are signed in units of 1e-6 and sizes carry two decimals, leaving at most
+/// four for the price.
+const SIZE_DECIMALS: u32 = 2;
+const MAX_PRICE_DECIMALS: u32 = 6 - SIZE_DECIMALS;
 
 fn decimal_to_token_u32(amt: Decimal) -> u32 {
     let mut amt = Decimal::from_scientific("1e6").expect("1e6 is not scientific") * amt;
@@ -114,6 +107,170 @@ fn decimal_to_token_u32(amt: Decimal) -> u32 {
     amt.try_into().expect("Couldn't round decimal to integer")
 }
 
+fn fix_amount_rounding(mut amt: Decimal, round_config: &RoundConfig) -> Decimal {
+    if amt.scale() > round_config.amount {
+        amt = amt.round_dp_with_strategy(round_config.amount + 4, AwayFromZero);
+        if amt.scale() > round_config.amount {
+            amt = amt.round_dp_with_strategy(round_config.amount, ToZero);
+        }
+    }
+    amt
+}
+
+fn get_order_amounts(
+    side: Side,
+    size: Decimal,
+    price: Decimal,
+    round_config: &RoundConfig,
+) -> (u32, u32) {
+    let raw_price = round_price(price, round_config);
+
+    match side {
+        Side::BUY => {
+            let raw_taker_amt = size.round_dp_with_strategy(round_config.size, ToZero);
+            let raw_maker_amt = raw_taker_amt * raw_price;
+            let raw_maker_amt = fix_amount_rounding(raw_maker_amt, round_config);
+            (
+                decimal_to_token_u32(raw_maker_amt),
+                decimal_to_token_u32(raw_taker_amt),
+            )
+        }
+        Side::SELL => {
+            let raw_maker_amt = size.round_dp_with_strategy(round_config.size, ToZero);
+            let raw_taker_amt = raw_maker_amt * raw_price;
+            let raw_taker_amt = fix_amount_rounding(raw_taker_amt, round_config);
+
+            (
+                decimal_to_token_u32(raw_maker_amt),
+                decimal_to_token_u32(raw_taker_amt),
+            )
+        }
+    }
+}
+
+fn get_market_order_amounts(
+    amount: Decimal,
+    price: Decimal,
+    round_config: &RoundConfig,
+) -> (u32, u32) {
+    let raw_maker_amt = amount.round_dp_with_strategy(round_config.size, ToZero);
+    let raw_price = round_price(price, round_config);
+
+    let raw_taker_amt = raw_maker_amt / raw_price;
+
+    let raw_taker_amt = fix_amount_rounding(raw_taker_amt, round_config);
+
+    (
+        decimal_to_token_u32(raw_maker_amt),
+        decimal_to_token_u32(raw_taker_amt),
+    )
+}
+
+fn round_config(tick_size: Decimal) -> Result<RoundConfig> {
+    let tick = tick_size.normalize();
+    if tick <= Decimal::ZERO || tick >= Decimal::ONE || tick.scale() > MAX_PRICE_DECIMALS {
+        return Err(anyhow!("Unsupported tick size {tick_size}"));
+    }
+    Ok(RoundConfig {
+        tick,
+        price: tick.scale(),
+        size: SIZE_DECIMALS,
+        amount: tick.scale() + SIZE_DECIMALS,
+    })
+}
+
+/// Rounds `price` to the nearest multiple of the tick, halfway cases towards zero.
+fn round_price(price: Decimal, round_config: &RoundConfig) -> Decimal {
+    let ticks = (price / round_config.tick).round_dp_with_strategy(0, MidpointTowardZero);
+    (ticks * round_config.tick).round_dp(round_config.price)
+}
+
+/// Rounds `price` to a multiple of `tick_size` in the passive direction: down for bids and
+/// up for asks, so the rounded order never crosses further than requested.
+pub fn round_price_to_tick(price: Decimal, tick_size: Decimal, side: Side) -> Result<Decimal> {
+    let round_config = round_config(tick_size)?;
+    let strategy = match side {
+        Side::BUY => ToNegativeInfinity,
+        Side::SELL => ToPositiveInfinity,
+    };
+    let ticks = (price / round_config.tick).round_dp_with_strategy(0, strategy);
+    Ok((ticks * round_config.tick).round_dp(round_config.price))
+}
+
+/// Maker and taker amounts, in token units, for a limit order rounded as [`OrderBuilder`] signs it.
+pub(crate) fn order_amounts(
+    side: Side,
+    size: Decimal,
+    price: Decimal,
+    tick_size: Decimal,
+) -> Result<(u32, u32)> {
+    Ok(get_order_amounts(
+        side,
+        size,
+        price,
+        &round_config(tick_size)?,
+    ))
+}
+
+/// Limit price and size in shares encoded by maker and taker amounts in token units.
+pub(crate) fn amounts_to_terms(side: Side, maker: Decimal, taker: Decimal) -> (Decimal, Decimal) {
+    let scale = Decimal::from(1_000_000);
+    match side {
+        Side::BUY => (maker / taker, taker / scale),
+        Side::SELL => (taker / maker, maker / scale),
+    }
+}
+
+/// Walks `asks` from the lowest price until `amount` USDC is spent.
+pub(crate) fn quote_market_buy(
+    asks: &[OrderSummary],
+    amount: Decimal,
+) -> Result<MarketOrderQuote, OrderValidationError> {
+    let mut asks = asks.to_vec();
+    asks.sort_by_key(|level| level.price);
+
+    let mut remaining = amount;
+    let mut levels = Vec::new();
+    for ask in asks {
+        if remaining <= Decimal::ZERO {
+            break;
+        }
+        let notional = ask.price * ask.size;
+        let size = if notional > remaining {
+            remaining / ask.price
+        } else {
+            ask.size
+        };
+        remaining -= notional.min(remaining);
+        levels.push(OrderSummary {
+            price: ask.price,
+            size,
+        });
+    }
+
+    let (Some(best), Some(worst)) = (levels.first(), levels.last()) else {
+        return Err(OrderValidationError::InsufficientLiquidity {
+            amount,
+            available: Decimal::ZERO,
+        });
+    };
+    if remaining > Decimal::ZERO {
+        return Err(OrderValidationError::InsufficientLiquidity {
+            amount,
+            available: amount - remaining,
+        });
+    }
+    let size: Decimal = levels.iter().map(|level| level.size).sum();
+    Ok(MarketOrderQuote {
+        best_price: best.price,
+        worst_price: worst.price,
+        average_price: amount / size,
+        size,
+        amount,
+        levels,
+    })
+}
+
 impl OrderBuilder {
     pub fn new(
         signer: Box<dyn EthSigner>,
@@ -134,86 +291,7 @@ impl OrderBuilder {
         self.sig_type as u8
     }
 
-    fn fix_amount_rounding(&self, mut amt: Decimal, round_config: &RoundConfig) -> Decimal {
-        if amt.scale() > round_config.amount {
-            amt = amt.round_dp_with_strategy(round_config.amount + 4, AwayFromZero);
-            if amt.scale() > round_config.amount {
-                amt = amt.round_dp_with_strategy(round_config.amount, ToZero);
-            }
-        }
-        amt
-    }
-
-    fn get_order_amounts(
-        &self,
-        side: Side,
-        size: Decimal,
-        price: Decimal,
-        round_config: &RoundConfig,
-    ) -> (u32, u32) {
-        let raw_price = price.round_dp_with_strategy(round_config.price, MidpointTowardZero);
-
-        match side {
-            Side::BUY => {
-                let raw_taker_amt = size.round_dp_with_strategy(round_config.size, ToZero);
-                let raw_maker_amt = raw_taker_amt * raw_price;
-                let raw_maker_amt = self.fix_amount_rounding(raw_maker_amt, round_config);
-                (
-                    decimal_to_token_u32(raw_maker_amt),
-                    decimal_to_token_u32(raw_taker_amt),
-                )
-            }
-            Side::SELL => {
-                let raw_maker_amt = size.round_dp_with_strategy(round_config.size, ToZero);
-                let raw_taker_amt = raw_maker_amt * raw_price;
-                let raw_taker_amt = self.fix_amount_rounding(raw_taker_amt, round_config);
-
-                (
-                    decimal_to_token_u32(raw_maker_amt),
-                    decimal_to_token_u32(raw_taker_amt),
-                )
-            }
-        }
-    }
-
-    fn get_market_order_amounts(
-        &self,
-        amount: Decimal,
-        price: Decimal,
-        round_config: &RoundConfig,
-    ) -> (u32, u32) {
-        let raw_maker_amt = amount.round_dp_with_strategy(round_config.size, ToZero);
-        let raw_price = price.round_dp_with_strategy(round_config.price, MidpointTowardZero);
-
-        let raw_taker_amt = raw_maker_amt / raw_price;
-
-        let raw_taker_amt = self.fix_amount_rounding(raw_taker_amt, round_config);
-
-        (
-            decimal_to_token_u32(raw_maker_amt),
-            decimal_to_token_u32(raw_taker_amt),
-        )
-    }
-
-    pub fn calculate_market_price(
-        &self,
-        positions: &[OrderSummary],
-        amount_to_match: Decimal,
-    ) -> Result<Decimal> {
-        let mut sum = Decimal::ZERO;
-
-        for p in positions {
-            sum += p.size * p.price;
-            if sum >= amount_to_match {
-                return Ok(p.price);
-            }
-        }
-        Err(anyhow!(
-            "Not enough liquidity to create market order with amount {amount_to_match}"
-        ))
-    }
-
-    pub fn create_market_order(
+    pub async fn create_market_order(
         &self,
         chain_id: u64,
         order_args: &MarketOrderArgs,
@@ -221,12 +299,14 @@ impl OrderBuilder {
         extras: &ExtraOrderArgs,
         options: CreateOrderOptions,
     ) -> Result<SignedOrderRequest> {
-        let (maker_amount, taker_amount) = self.get_market_order_amounts(
+        let (maker_amount, taker_amount) = get_market_order_amounts(
             order_args.amount,
             price,
-            &ROUNDING_CONFIG[&options
-                .tick_size
-                .context("Cannot create order without tick size")?],
+            &round_config(
+                options
+                    .tick_size
+                    .context("Cannot create order without tick size")?,
+            )?,
         );
 
         let contract_config = get_contract_config(
@@ -250,9 +330,10 @@ impl OrderBuilder {
             0,
             extras,
         )
+        .await
     }
 
-    pub fn create_order(
+    pub async fn create_order(
         &self,
         chain_id: u64,
         order_args: &OrderArgs,
@@ -260,13 +341,15 @@ impl OrderBuilder {
         extras: &ExtraOrderArgs,
         options: CreateOrderOptions,
     ) -> Result<SignedOrderRequest> {
-        let (maker_amount, taker_amount) = self.get_order_amounts(
+        let (maker_amount, taker_amount) = get_order_amounts(
             order_args.side,
             order_args.size,
             order_args.price,
-            &ROUNDING_CONFIG[&options
-                .tick_size
-                .context("Cannot create order without tick size")?],
+            &round_config(
+                options
+                    .tick_size
+                    .context("Cannot create order without tick size")?,
+            )?,
         );
 
         let contract_config = get_contract_config(
@@ -290,10 +373,11 @@ impl OrderBuilder {
             expiration,
             extras,
         )
+        .await
     }
 
     #[allow(clippy::too_many_arguments)]
-    fn build_signed_order(
+    async fn build_signed_order(
         &self,
         token_id: String,
         side: Side,
@@ -304,6 +388,9 @@ impl OrderBuilder {
         expiration: u64,
         extras: &ExtraOrderArgs,
     ) -> Result<SignedOrderRequest> {
+        let fee_rate_bps = extras
+            .fee_rate_bps
+            .context("Cannot create order without fee rate")?;
         let seed = generate_seed();
         let taker_address =
             Address::from_str(extras.taker.as_ref()).context("Invalid taker address")?;
@@ -321,12 +408,20 @@ impl OrderBuilder {
             takerAmount: U256::from(taker_amount),
             expiration: U256::from(expiration),
             nonce: extras.nonce,
-            feeRateBps: U256::from(extras.fee_rate_bps),
+            feeRateBps: U256::from(fee_rate_bps),
             side: side as u8,
             signatureType: self.sig_type as u8,
         };
 
-        let signature = sign_order_message(&self.signer, order, chain_id, exchange)?;
+        let signature = sign_order_message(&self.signer, order, chain_id, exchange).await?;
+        tracing::debug!(
+            token_id = %token_id,
+            side = side.as_str(),
+            maker_amount,
+            taker_amount,
+            expiration,
+            "signed order"
+        );
 
         Ok(SignedOrderRequest {
             salt: seed,
@@ -338,10 +433,86 @@ impl OrderBuilder {
             taker_amount: taker_amount.to_string(),
             expiration: expiration.to_string(),
             nonce: extras.nonce.to_string(),
-            fee_rate_bps: extras.fee_rate_bps.to_string(),
+            fee_rate_bps: fee_rate_bps.to_string(),
             side: side.as_str().into(),
             signature_type: self.sig_type as u8,
             signature,
+            post_only: false,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec(s: &str) -> Decimal {
+        Decimal::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn test_quote_market_buy() {
+        let asks = [
+            OrderSummary {
+                price: dec("0.6"),
+                size: dec("100"),
+            },
+            OrderSummary {
+                price: dec("0.5"),
+                size: dec("10"),
+            },
+        ];
+        let quote = quote_market_buy(&asks, dec("11")).unwrap();
+        assert_eq!(quote.best_price, dec("0.5"));
+        assert_eq!(quote.worst_price, dec("0.6"));
+        assert_eq!(quote.size, dec("20"));
+        assert_eq!(quote.average_price, dec("0.55"));
+        assert_eq!(quote.levels.len(), 2);
+
+        assert_eq!(
+            quote_market_buy(&asks, dec("100")).unwrap_err(),
+            OrderValidationError::InsufficientLiquidity {
+                amount: dec("100"),
+                available: dec("65"),
+            }
+        );
+    }
+
+    #[test]
+    fn test_rounding_from_tick_size() {
+        assert_eq!(
+            get_order_amounts(
+                Side::BUY,
+                dec("10"),
+                dec("0.4567"),
+                &round_config(dec("0.01")).unwrap()
+            ),
+            (4_600_000, 10_000_000)
+        );
+        // Non power of ten ticks snap to the nearest multiple.
+        assert_eq!(
+            get_order_amounts(
+                Side::SELL,
+                dec("10"),
+                dec("0.43"),
+                &round_config(dec("0.05")).unwrap()
+            ),
+            (10_000_000, 4_500_000)
+        );
+        assert!(round_config(dec("0.00001")).is_err());
+        assert!(round_config(dec("0")).is_err());
+
+        assert_eq!(
+            round_price_to_tick(dec("0.457"), dec("0.01"), Side::BUY).unwrap(),
+            dec("0.45")
+        );
+        assert_eq!(
+            round_price_to_tick(dec("0.451"), dec("0.01"), Side::SELL).unwrap(),
+            dec("0.46")
+        );
+        assert_eq!(
+            round_price_to_tick(dec("0.45"), dec("0.01"), Side::SELL).unwrap(),
+            dec("0.45")
+        );
+    }
+}
@@ -0,0 +1,795 @@
+//! In-memory matching engine for paper trading.
+//!
+//! [`PaperExchange`] accepts the same [`SignedOrderRequest`]s as the live API and
+//! matches them with price-time priority against books seeded from
+//! `get_order_book` snapshots. Signatures are not checked and balances are not tracked.
+
+use crate::fees::calculate_fee;
+use crate::utils::get_current_unix_time_secs;
+use crate::{
+    ClientResult, ClobClient, OpenOrder, OpenOrderParams, OrderBookSummary, OrderType, OrderVenue,
+    Side, SignedOrderRequest, TradeParams,
+};
+use anyhow::{anyhow, Context};
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::{Mutex, MutexGuard};
+
+/// Owner reported on paper orders and trades.
+pub const PAPER_OWNER: &str = "paper";
+
+const LIVE: &str = "LIVE";
+const MATCHED: &str = "MATCHED";
+const CANCELED: &str = "CANCELED";
+
+/// A queue entry at a price level. Liquidity from book snapshots has no order id.
+struct Resting {
+    order_id: Option<String>,
+    size: Decimal,
+}
+
+type Levels = BTreeMap<Decimal, VecDeque<Resting>>;
+
+#[derive(Default)]
+struct Book {
+    market: String,
+    bids: Levels,
+    asks: Levels,
+}
+
+impl Book {
+    fn side_mut(&mut self, side: Side) -> &mut Levels {
+        match side {
+            Side::BUY => &mut self.bids,
+            Side::SELL => &mut self.asks,
+        }
+    }
+}
+
+fn opposite(side: Side) -> Side {
+    match side {
+        Side::BUY => Side::SELL,
+        Side::SELL => Side::BUY,
+    }
+}
+
+/// Best price among levels resting on `side`.
+fn best_price(levels: &Levels, side: Side) -> Option<Decimal> {
+    match side {
+        Side::BUY => levels.keys().next_back().copied(),
+        Side::SELL => levels.keys().next().copied(),
+    }
+}
+
+fn crosses(taker_side: Side, limit: Decimal, maker_price: Decimal) -> bool {
+    match taker_side {
+        Side::BUY => maker_price <= limit,
+        Side::SELL => maker_price >= limit,
+    }
+}
+
+fn dec_str(d: Decimal) -> String {
+    d.normalize().to_string()
+}
+
+struct Fill {
+    maker_order_id: Option<String>,
+    price: Decimal,
+    size: Decimal,
+}
+
+/// Size a taker on `taker_side` limited at `limit` could fill against `levels`.
+fn available(levels: &Levels, taker_side: Side, limit: Decimal) -> Decimal {
+    levels
+        .iter()
+        .filter(|(price, _)| crosses(taker_side, limit, **price))
+        .flat_map(|(_, queue)| queue.iter().map(|r| r.size))
+        .sum()
+}
+
+/// Removes up to `size` from `levels`, best price first and oldest first within a price.
+fn take(levels: &mut Levels, taker_side: Side, limit: Decimal, mut size: Decimal) -> Vec<Fill> {
+    let mut fills = Vec::new();
+    while size > Decimal::ZERO {
+        let Some(price) = best_price(levels, opposite(taker_side)) else {
+            break;
+        };
+        if !crosses(taker_side, limit, price) {
+            break;
+        }
+        let queue = levels.get_mut(&price).expect("Best level exists");
+        while size > Decimal::ZERO {
+            let Some(front) = queue.front_mut() else {
+                break;
+            };
+            let matched = front.size.min(size);
+            front.size -= matched;
+            size -= matched;
+            fills.push(Fill {
+                maker_order_id: front.order_id.clone(),
+                price,
+                size: matched,
+            });
+            if front.size.is_zero() {
+                queue.pop_front();
+            }
+        }
+        if queue.is_empty() {
+            levels.remove(&price);
+        }
+    }
+    fills
+}
+
+struct PaperOrder {
+    id: String,
+    market: String,
+    asset_id: String,
+    side: Side,
+    price: Decimal,
+    original_size: Decimal,
+    size_matched: Decimal,
+    status: &'static str,
+    maker_address: String,
+    order_type: OrderType,
+    expiration: u64,
+    fee_rate_bps: u32,
+    created_at: u64,
+    associate_trades: Vec<String>,
+}
+
+impl PaperOrder {
+    fn remaining(&self) -> Decimal {
+        self.original_size - self.size_matched
+    }
+
+    fn to_open_order(&self) -> OpenOrder {
+        OpenOrder {
+            associate_trades: self.associate_trades.clone(),
+            id: self.id.clone(),
+            status: self.status.to_owned(),
+            market: Some(self.market.clone()),
+            original_size: Some(self.original_size),
+            outcome: None,
+            maker_address: Some(self.maker_address.clone()),
+            owner: Some(PAPER_OWNER.to_owned()),
+            price: Some(self.price),
+            side: Some(self.side),
+            size_matched: Some(self.size_matched),
+            asset_id: Some(self.asset_id.clone()),
+            expiration: Some(self.expiration),
+            order_type: Some(self.order_type),
+            created_at: Some(self.created_at),
+        }
+    }
+}
+
+#[derive(Default)]
+struct PaperState {
+    clock: Option<u64>,
+    next_id: u64,
+    books: HashMap<String, Book>,
+    orders: Vec<PaperOrder>,
+    trades: Vec<Value>,
+}
+
+impl PaperState {
+    fn now(&self) -> u64 {
+        self.clock.unwrap_or_else(get_current_unix_time_secs)
+    }
+
+    fn next_id(&mut self) -> String {
+        self.next_id += 1;
+        format!("0x{:064x}", self.next_id)
+    }
+
+    fn order_mut(&mut self, id: &str) -> Option<&mut PaperOrder> {
+        self.orders.iter_mut().find(|o| o.id == id)
+    }
+
+    /// Removes a paper order from its book queue.
+    fn unlink(&mut self, id: &str) {
+        let Some(order) = self.orders.iter().find(|o| o.id == id) else {
+            return;
+        };
+        let (side, price) = (order.side, order.price);
+        let Some(book) = self.books.get_mut(&order.asset_id) else {
+            return;
+        };
+        let levels = book.side_mut(side);
+        if let Some(queue) = levels.get_mut(&price) {
+            queue.retain(|r| r.order_id.as_deref() != Some(id));
+            if queue.is_empty() {
+                levels.remove(&price);
+            }
+        }
+    }
+
+    /// Cancels GTD orders whose expiration has passed.
+    fn expire(&mut self) {
+        let now = self.now();
+        let expired: Vec<_> = self
+            .orders
+            .iter()
+            .filter(|o| {
+                o.status == LIVE
+                    && o.order_type == OrderType::GTD
+                    && o.expiration != 0
+                    && o.expiration <= now
+            })
+            .map(|o| o.id.clone())
+            .collect();
+        for id in expired {
+            self.unlink(&id);
+            if let Some(order) = self.order_mut(&id) {
+                order.status = CANCELED;
+            }
+        }
+    }
+
+    /// Applies a fill to the paper orders involved and records a trade for each of them.
+    /// `taker_id` is `None` when the taker is liquidity from a book snapshot. As in the
+    /// live API, `side`, `size` and `price` describe the taker and a maker's own fill is
+    /// listed in `maker_orders`; `fee` is what the recording order paid.
+    fn settle(&mut self, token_id: &str, taker_side: Side, taker_id: Option<&str>, fill: &Fill) {
+        let match_time = self.now().to_string();
+        let find = |id: Option<&str>| self.orders.iter().find(|o| Some(o.id.as_str()) == id);
+        let taker_fee_rate_bps = find(taker_id).map_or(0, |o| o.fee_rate_bps);
+        let mut maker_order = json!({
+            "order_id": fill.maker_order_id.clone().unwrap_or_default(),
+            "matched_amount": dec_str(fill.size),
+            "price": dec_str(fill.price),
+            "asset_id": token_id,
+            "side": opposite(taker_side).as_str(),
+        });
+        if let Some(maker) = find(fill.maker_order_id.as_deref()) {
+            maker_order["owner"] = json!(PAPER_OWNER);
+            maker_order["maker_address"] = json!(maker.maker_address);
+            maker_order["fee_rate_bps"] = json!(maker.fee_rate_bps.to_string());
+        }
+        let maker_orders = json!([maker_order]);
+
+        for (id, trader_side) in [
+            (taker_id, "TAKER"),
+            (fill.maker_order_id.as_deref(), "MAKER"),
+        ] {
+            let Some(id) = id else {
+                continue;
+            };
+            let trade_id = self.next_id();
+            let Some(order) = self.order_mut(id) else {
+                continue;
+            };
+            order.size_matched += fill.size;
+            if order.remaining().is_zero() {
+                order.status = MATCHED;
+            }
+            order.associate_trades.push(trade_id.clone());
+            let fee = calculate_fee(order.fee_rate_bps, fill.price, fill.size);
+            let trade = json!({
+                "id": trade_id,
+                "taker_order_id": taker_id.unwrap_or_default(),
+                "market": order.market,
+                "asset_id": token_id,
+                "side": taker_side.as_str(),
+                "size": dec_str(fill.size),
+                "price": dec_str(fill.price),
+                "fee_rate_bps": taker_fee_rate_bps.to_string(),
+                "fee": dec_str(fee),
+                "status": MATCHED,
+                "match_time": match_time,
+                "outcome": "",
+                "owner": PAPER_OWNER,
+                "maker_address": order.maker_address,
+                "trader_side": trader_side,
+                "maker_orders": maker_orders,
+            });
+            self.trades.push(trade);
+        }
+    }
+
+    /// Replaces the snapshot liquidity of a book, keeping paper orders in place.
+    fn seed(&mut self, summary: &OrderBookSummary) {
+        self.expire();
+        let book = self.books.entry(summary.asset_id.clone()).or_default();
+        book.market = summary.market.clone();
+        for (levels, snapshot) in [
+            (&mut book.bids, &summary.bids),
+            (&mut book.asks, &summary.asks),
+        ] {
+            for queue in levels.values_mut() {
+                queue.retain(|r| r.order_id.is_some());
+            }
+            levels.retain(|_, queue| !queue.is_empty());
+            // Snapshot liquidity is assumed to have arrived before any paper order at the same price.
+            for level in snapshot.iter().filter(|l| l.size > Decimal::ZERO) {
+                levels.entry(level.price).or_default().push_front(Resting {
+                    order_id: None,
+                    size: level.size,
+                });
+            }
+        }
+        self.uncross(&summary.asset_id);
+    }
+
+    /// Fills resting paper orders that a new snapshot trades through, at their own price.
+    fn uncross(&mut self, token_id: &str) {
+        loop {
+            let Some(book) = self.books.get_mut(token_id) else {
+                return;
+            };
+            let (Some(bid), Some(ask)) = (
+                best_price(&book.bids, Side::BUY),
+                best_price(&book.asks, Side::SELL),
+            ) else {
+                return;
+            };
+            if bid < ask {
+                return;
+            }
+            let bid_id = book.bids[&bid][0].order_id.clone();
+            let ask_id = book.asks[&ask][0].order_id.clone();
+            let (taker_side, price, maker_id, taker_id) = match (bid_id, ask_id) {
+                (Some(bid_id), ask_id) => (Side::SELL, bid, Some(bid_id), ask_id),
+                (None, Some(ask_id)) => (Side::BUY, ask, Some(ask_id), None),
+                (None, None) => return,
+            };
+            let size = book.bids[&bid][0].size.min(book.asks[&ask][0].size);
+            for (levels, level) in [(&mut book.bids, bid), (&mut book.asks, ask)] {
+                let queue = levels.get_mut(&level).expect("Best level exists");
+                queue[0].size -= size;
+                if queue[0].size.is_zero() {
+                    queue.pop_front();
+                }
+                if queue.is_empty() {
+                    levels.remove(&level);
+                }
+            }
+            let fill = Fill {
+                maker_order_id: maker_id,
+                price,
+                size,
+            };
+            self.settle(token_id, taker_side, taker_id.as_deref(), &fill);
+        }
+    }
+
+    fn post(&mut self, order: SignedOrderRequest, order_type: OrderType) -> ClientResult<Value> {
+        let (side, price, size) = order.terms()?;
+        let expiration: u64 = order.expiration.parse().context("Invalid expiration")?;
+        let fee_rate_bps: u32 = order.fee_rate_bps.parse().context("Invalid fee rate")?;
+        self.expire();
+        let now = self.now();
+        if order_type == OrderType::GTD && expiration <= now {
+            return Err(anyhow!(
+                "invalid order params, expiration {expiration} is in the past"
+            ));
+        }
+
+        let token_id = order.token_id;
+        let book = self.books.entry(token_id.clone()).or_default();
+        let market = book.market.clone();
+        let levels = book.side_mut(opposite(side));
+        if order.post_only && available(levels, side, price) > Decimal::ZERO {
+            return Err(anyhow!("invalid post-only order: order crosses book"));
+        }
+        if order_type == OrderType::FOK && available(levels, side, price) < size {
+            return Err(anyhow!(
+                "order couldn't be fully filled. FOK orders are fully filled or killed."
+            ));
+        }
+        let fills = take(levels, side, price, size);
+        if order_type == OrderType::FAK && fills.is_empty() {
+            return Err(anyhow!(
+                "no orders found to match with FAK order. FAK orders are partially filled or killed if no match is found."
+            ));
+        }
+
+        let id = self.next_id();
+        self.orders.push(PaperOrder {
+            id: id.clone(),
+            market,
+            asset_id: token_id.clone(),
+            side,
+            price,
+            original_size: size,
+            size_matched: Decimal::ZERO,
+            status: LIVE,
+            maker_address: order.maker,
+            order_type,
+            expiration,
+            fee_rate_bps,
+            created_at: now,
+            associate_trades: Vec::new(),
+        });
+        for fill in &fills {
+            self.settle(&token_id, side, Some(&id), fill);
+        }
+
+        let filled: Decimal = fills.iter().map(|f| f.size).sum();
+        let notional: Decimal = fills.iter().map(|f| f.size * f.price).sum();
+        let status = if filled == size {
+            "matched"
+        } else if order_type == OrderType::FAK {
+            // The unfilled rest of a FAK order never rests.
+            if let Some(order) = self.order_mut(&id) {
+                order.status = MATCHED;
+            }
+            "matched"
+        } else {
+            self.books
+                .get_mut(&token_id)
+                .expect("Book was created above")
+                .side_mut(side)
+                .entry(price)
+                .or_default()
+                .push_back(Resting {
+                    order_id: Some(id.clone()),
+                    size: size - filled,
+                });
+            "live"
+        };
+        let (making, taking) = match side {
+            Side::BUY => (notional, filled),
+            Side::SELL => (filled, notional),
+        };
+        tracing::debug!(order_id = %id, token_id = %token_id, side = side.as_str(), %price, %size, %filled, "paper order");
+
+        Ok(json!({
+            "success": true,
+            "errorMsg": "",
+            "orderID": id,
+            "status": status,
+            "makingAmount": dec_str(making),
+            "takingAmount": dec_str(taking),
+            "orderHashes": [],
+        }))
+    }
+
+    fn cancel_where(&mut self, matches: impl Fn(&PaperOrder) -> bool) -> Vec<String> {
+        self.expire();
+        let ids: Vec<_> = self
+            .orders
+            .iter()
+            .filter(|o| o.status == LIVE && matches(o))
+            .map(|o| o.id.clone())
+            .collect();
+        for id in &ids {
+            self.unlink(id);
+            if let Some(order) = self.order_mut(id) {
+                order.status = CANCELED;
+            }
+        }
+        ids
+    }
+
+    fn cancel_ids(&mut self, order_ids: &[String]) -> Value {
+        let canceled = self.cancel_where(|o| order_ids.contains(&o.id));
+        let not_canceled: serde_json::Map<_, _> = order_ids
+            .iter()
+            .filter(|id| !canceled.contains(id))
+            .map(|id| (id.clone(), json!("Order not found or already canceled")))
+            .collect();
+        json!({ "canceled": canceled, "not_canceled": not_canceled })
+    }
+}
+
+/// A simulated exchange that can stand in for [`ClobClient`] through [`OrderVenue`].
+///
+/// Books are seeded with [`seed_book`](PaperExchange::seed_book) or
+/// [`sync_book`](PaperExchange::sync_book). Snapshot liquidity consumed by paper
+/// orders stays consumed until the next snapshot. Every order pays its own
+/// `fee_rate_bps` on each fill, reported in the `fee` field of its trades.
+#[derive(Default)]
+pub struct PaperExchange {
+    state: Mutex<PaperState>,
+}
+
+impl PaperExchange {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the snapshot liquidity for `book.asset_id`. Resting paper orders keep
+    /// their place; any that the snapshot trades through are filled at their own price.
+    pub fn seed_book(&self, book: &OrderBookSummary) {
+        self.state().seed(book);
+    }
+
+    /// Fetches the current book for `token_id` and seeds it.
+    pub async fn sync_book(&self, client: &ClobClient, token_id: &str) -> ClientResult<()> {
+        let book = client.get_order_book(token_id).await?;
+        self.seed_book(&book);
+        Ok(())
+    }
+
+    /// Fixes the clock used for GTD expiry and trade timestamps. `None` uses the system clock.
+    pub fn set_time(&self, now: Option<u64>) {
+        self.state().clock = now;
+    }
+
+    fn state(&self) -> MutexGuard<'_, PaperState> {
+        self.state.lock().expect("Paper exchange lock poisoned")
+    }
+}
+
+#[async_trait]
+impl OrderVenue for PaperExchange {
+    async fn post_order(
+        &self,
+        order: SignedOrderRequest,
+        order_type: OrderType,
+    ) -> ClientResult<Value> {
+        self.state().post(order, order_type)
+    }
+
+    async fn cancel(&self, order_id: &str) -> ClientResult<Value> {
+        Ok(self.state().cancel_ids(&[order_id.to_owned()]))
+    }
+
+    async fn cancel_orders(&self, order_ids: &[String]) -> ClientResult<Value> {
+        Ok(self.state().cancel_ids(order_ids))
+    }
+
+    async fn cancel_all(&self) -> ClientResult<Value> {
+        let canceled = self.state().cancel_where(|_| true);
+        Ok(json!({ "canceled": canceled, "not_canceled": {} }))
+    }
+
+    async fn cancel_market_orders(
+        &self,
+        market: Option<&str>,
+        asset_id: Option<&str>,
+    ) -> ClientResult<Value> {
+        let market = market.filter(|m| !m.is_empty());
+        let asset_id = asset_id.filter(|a| !a.is_empty());
+        let canceled = self.state().cancel_where(|o| {
+            market.is_none_or(|m| o.market == m) && asset_id.is_none_or(|a| o.asset_id == a)
+        });
+        Ok(json!({ "canceled": canceled, "not_canceled": {} }))
+    }
+
+    async fn get_order(&self, order_id: &str) -> ClientResult<Option<OpenOrder>> {
+        let mut state = self.state();
+        state.expire();
+        Ok(state
+            .orders
+            .iter()
+            .find(|o| o.id == order_id)
+            .map(PaperOrder::to_open_order))
+    }
+
+    async fn get_orders(
+        &self,
+        params: Option<&OpenOrderParams>,
+        _next_cursor: Option<&str>,
+    ) -> ClientResult<Vec<OpenOrder>> {
+        let mut state = self.state();
+        state.expire();
+        Ok(state
+            .orders
+            .iter()
+            .filter(|o| o.status == LIVE)
+            .filter(|o| {
+                params.is_none_or(|p| {
+                    p.id.as_ref().is_none_or(|id| &o.id == id)
+                        && p.asset_id.as_ref().is_none_or(|a| &o.asset_id == a)
+                        && p.market.as_ref().is_none_or(|m| &o.market == m)
+                })
+            })
+            .map(PaperOrder::to_open_order)
+            .collect())
+    }
+
+    /// Returns all matching trades as a single page, like [`ClobClient::get_trades`].
+    async fn get_trades(
+        &self,
+        trade_params: Option<&TradeParams>,
+        _next_cursor: Option<&str>,
+    ) -> ClientResult<Vec<Value>> {
+        let state = self.state();
+        let field = |t: &Value, k: &str| t[k].as_str().unwrap_or_default().to_owned();
+        let trades = state
+            .trades
+            .iter()
+            .filter(|t| {
+                let Some(p) = trade_params else {
+                    return true;
+                };
+                let time: u64 = field(t, "match_time").parse().unwrap_or_default();
+                p.id.as_ref().is_none_or(|id| &field(t, "id") == id)
+                    && p.maker_address
+                        .as_ref()
+                        .is_none_or(|a| field(t, "maker_address").eq_ignore_ascii_case(a))
+                    && p.market.as_ref().is_none_or(|m| &field(t, "market") == m)
+                    && p.asset_id
+                        .as_ref()
+                        .is_none_or(|a| &field(t, "asset_id") == a)
+                    && p.before.is_none_or(|b| time < b)
+                    && p.after.is_none_or(|a| time > a)
+            })
+            .cloned()
+            .collect();
+        Ok(vec![Value::Array(trades)])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::OrderSummary;
+    use std::str::FromStr;
+
+    fn dec(s: &str) -> Decimal {
+        Decimal::from_str(s).unwrap()
+    }
+
+    fn order(side: Side, price: &str, size: &str, expiration: u64) -> SignedOrderRequest {
+        let (price, size) = (dec(price), dec(size));
+        let units = |d: Decimal| (d * Decimal::from(1_000_000)).normalize().to_string();
+        let (maker_amount, taker_amount) = match side {
+            Side::BUY => (units(price * size), units(size)),
+            Side::SELL => (units(size), units(price * size)),
+        };
+        SignedOrderRequest {
+            salt: 1,
+            maker: "0xmaker".into(),
+            signer: "0xmaker".into(),
+            taker: "0x0000000000000000000000000000000000000000".into(),
+            token_id: "1".into(),
+            maker_amount,
+            taker_amount,
+            expiration: expiration.to_string(),
+            nonce: "0".into(),
+            fee_rate_bps: "100".into(),
+            side: side.as_str().into(),
+            signature_type: 0,
+            signature: "0x".into(),
+            post_only: false,
+        }
+    }
+
+    fn book(bids: &[(&str, &str)], asks: &[(&str, &str)]) -> OrderBookSummary {
+        let levels = |l: &[(&str, &str)]| {
+            l.iter()
+                .map(|(p, s)| OrderSummary {
+                    price: dec(p),
+                    size: dec(s),
+                })
+                .collect()
+        };
+        OrderBookSummary {
+            market: "0xmarket".into(),
+            asset_id: "1".into(),
+            hash: String::new(),
+            timestamp: 0,
+            bids: levels(bids),
+            asks: levels(asks),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_paper_partial_fill_rests_and_fills_on_snapshot() {
+        let paper = PaperExchange::new();
+        paper.seed_book(&book(&[("0.48", "50")], &[("0.5", "10"), ("0.52", "10")]));
+
+        let resp = paper
+            .post_order(order(Side::BUY, "0.51", "15", 0), OrderType::GTC)
+            .await
+            .unwrap();
+        assert_eq!(resp["status"], "live");
+        assert_eq!(resp["makingAmount"], "5");
+        assert_eq!(resp["takingAmount"], "10");
+        let id = resp["orderID"].as_str().unwrap();
+
+        let open = paper.get_orders(None, None).await.unwrap();
+        assert_eq!(open.len(), 1);
+        assert_eq!(open[0].size_matched, Some(dec("10")));
+
+        let trades = paper.get_trades(None, None).await.unwrap();
+        let trades = trades[0].as_array().unwrap();
+        assert_eq!(trades.len(), 1);
+        assert_eq!(trades[0]["price"], "0.5");
+        assert_eq!(trades[0]["fee"], "0.05");
+        assert_eq!(trades[0]["trader_side"], "TAKER");
+
+        // A seller trading through the resting bid fills it at the bid price.
+        paper.seed_book(&book(&[], &[("0.51", "100")]));
+        let filled = paper.get_order(id).await.unwrap().unwrap();
+        assert_eq!(filled.status, MATCHED);
+        let trades = paper.get_trades(None, None).await.unwrap();
+        let last = trades[0].as_array().unwrap().last().unwrap().clone();
+        assert_eq!(last["trader_side"], "MAKER");
+        assert_eq!(last["size"], "5");
+        assert_eq!(last["price"], "0.51");
+    }
+
+    #[tokio::test]
+    async fn test_paper_fok_and_price_time_priority() {
+        let paper = PaperExchange::new();
+        paper.seed_book(&book(&[], &[]));
+        let first = paper
+            .post_order(order(Side::SELL, "0.6", "10", 0), OrderType::GTC)
+            .await
+            .unwrap();
+        let second = paper
+            .post_order(order(Side::SELL, "0.6", "10", 0), OrderType::GTC)
+            .await
+            .unwrap();
+
+        let err = paper
+            .post_order(order(Side::BUY, "0.6", "30", 0), OrderType::FOK)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("FOK"));
+
+        let resp = paper
+            .post_order(order(Side::BUY, "0.6", "15", 0), OrderType::FOK)
+            .await
+            .unwrap();
+        assert_eq!(resp["status"], "matched");
+
+        let first = paper
+            .get_order(first["orderID"].as_str().unwrap())
+            .await
+            .unwrap()
+            .unwrap();
+        let second = paper
+            .get_order(second["orderID"].as_str().unwrap())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(first.status, MATCHED);
+        assert_eq!(second.size_matched, Some(dec("5")));
+
+        let resp = paper
+            .post_order(order(Side::BUY, "0.6", "10", 0), OrderType::FAK)
+            .await
+            .unwrap();
+        assert_eq!(resp["takingAmount"], "5");
+        let err = paper
+            .post_order(order(Side::BUY, "0.6", "10", 0), OrderType::FAK)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("FAK"));
+    }
+
+    #[tokio::test]
+    async fn test_paper_gtd_expiry_and_cancel() {
+        let paper = PaperExchange::new();
+        paper.set_time(Some(1_000));
+        paper.seed_book(&book(&[], &[]));
+
+        assert!(paper
+            .post_order(order(Side::BUY, "0.4", "10", 1_000), OrderType::GTD)
+            .await
+            .is_err());
+        let gtd = paper
+            .post_order(order(Side::BUY, "0.4", "10", 1_010), OrderType::GTD)
+            .await
+            .unwrap();
+        let gtc = paper
+            .post_order(order(Side::BUY, "0.3", "10", 0), OrderType::GTC)
+            .await
+            .unwrap();
+        assert_eq!(paper.get_orders(None, None).await.unwrap().len(), 2);
+
+        paper.set_time(Some(1_010));
+        let open = paper.get_orders(None, None).await.unwrap();
+        assert_eq!(open.len(), 1);
+        assert_eq!(open[0].id, gtc["orderID"].as_str().unwrap());
+
+        let resp = paper
+            .cancel_orders(&[
+                gtc["orderID"].as_str().unwrap().to_owned(),
+                gtd["orderID"].as_str().unwrap().to_owned(),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(resp["canceled"].as_array().unwrap().len(), 1);
+        assert_eq!(resp["not_canceled"].as_object().unwrap().len(), 1);
+        assert!(paper.get_orders(None, None).await.unwrap().is_empty());
+    }
+}
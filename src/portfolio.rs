@@ -0,0 +1,338 @@
+//! Positions and PnL built from our own trades.
+
+use crate::fees::calculate_fee;
+use crate::{AssetType, BalanceAllowanceParams, ClientResult, ClobClient, Side, Trade, Value};
+use anyhow::Context;
+use rust_decimal::Decimal;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+/// Holdings of one outcome token. Positions are signed: selling more than is held
+/// opens a short at the sale price.
+#[derive(Debug, Clone, Default)]
+pub struct Position {
+    pub token_id: String,
+    pub market: String,
+    pub size: Decimal,
+    /// Average entry price of the open size, excluding fees.
+    pub avg_cost: Decimal,
+    /// PnL from closed size, net of all fees paid on this token.
+    pub realized_pnl: Decimal,
+    pub fees: Decimal,
+    /// Last mark set by [`Portfolio::set_mark`] or [`Portfolio::mark_to_market`].
+    pub mark: Option<Decimal>,
+}
+
+impl Position {
+    /// PnL of the open size at the current mark; zero until a mark is set.
+    pub fn unrealized_pnl(&self) -> Decimal {
+        self.mark
+            .map(|mark| (mark - self.avg_cost) * self.size)
+            .unwrap_or_default()
+    }
+
+    fn apply_fill(&mut self, side: Side, price: Decimal, size: Decimal, fee: Decimal) {
+        self.fees += fee;
+        self.realized_pnl -= fee;
+
+        let signed = match side {
+            Side::BUY => size,
+            Side::SELL => -size,
+        };
+        if self.size.is_zero() || self.size.is_sign_positive() == signed.is_sign_positive() {
+            let new_size = self.size + signed;
+            self.avg_cost = (self.avg_cost * self.size.abs() + price * size) / new_size.abs();
+            self.size = new_size;
+            return;
+        }
+
+        let closed = size.min(self.size.abs());
+        self.realized_pnl += if self.size.is_sign_positive() {
+            (price - self.avg_cost) * closed
+        } else {
+            (self.avg_cost - price) * closed
+        };
+        let was_long = self.size.is_sign_positive();
+        self.size += signed;
+        if self.size.is_zero() {
+            self.avg_cost = Decimal::ZERO;
+        } else if self.size.is_sign_positive() != was_long {
+            self.avg_cost = price;
+        }
+    }
+}
+
+/// A token whose local position differs from the exchange balance.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PositionMismatch {
+    pub token_id: String,
+    pub local: Decimal,
+    pub exchange: Decimal,
+}
+
+/// Per-token positions and PnL built from trades.
+///
+/// Trades are attributed to us when `trader_side` is `TAKER`, or through the entries of
+/// `maker_orders` whose `owner` or `maker_address` matches one of our identities (the API
+/// key, the funder address, or [`PAPER_OWNER`](crate::PAPER_OWNER) for paper trades).
+#[derive(Debug, Default)]
+pub struct Portfolio {
+    owners: Vec<String>,
+    positions: HashMap<String, Position>,
+    seen: HashSet<String>,
+}
+
+impl Portfolio {
+    pub fn new(owners: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Portfolio {
+            owners: owners.into_iter().map(Into::into).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn is_ours(&self, owner: Option<&str>, address: Option<&str>) -> bool {
+        self.owners.iter().any(|o| {
+            owner.is_some_and(|x| x == o) || address.is_some_and(|x| x.eq_ignore_ascii_case(o))
+        })
+    }
+
+    /// Applies our side of `trade`. Trades already seen and failed trades are ignored.
+    pub fn ingest(&mut self, trade: &Trade) {
+        if trade.status == "FAILED" || !self.seen.insert(trade.id.clone()) {
+            return;
+        }
+        // A maker can be matched on the complement token, in which case the fill is in
+        // `m.asset_id` and on the same side as the taker rather than the opposite one.
+        let maker_fills: Vec<_> = trade
+            .maker_orders
+            .iter()
+            .filter(|m| self.is_ours(m.owner.as_deref(), m.maker_address.as_deref()))
+            .map(|m| {
+                let asset_id = m.asset_id.as_ref().unwrap_or(&trade.asset_id);
+                let side = match m.side {
+                    Some(side) => side,
+                    None if asset_id != &trade.asset_id => trade.side,
+                    None => match trade.side {
+                        Side::BUY => Side::SELL,
+                        Side::SELL => Side::BUY,
+                    },
+                };
+                (asset_id, side, m.price, m.matched_amount, m.fee_rate_bps)
+            })
+            .collect();
+        let is_taker = match trade.trader_side.as_deref() {
+            Some(side) => side == "TAKER",
+            None => {
+                maker_fills.is_empty()
+                    && self.is_ours(trade.owner.as_deref(), trade.maker_address.as_deref())
+            }
+        };
+        let fills = if is_taker {
+            vec![(
+                &trade.asset_id,
+                trade.side,
+                trade.price,
+                trade.size,
+                trade.fee_rate_bps,
+            )]
+        } else {
+            maker_fills
+        };
+
+        for (asset_id, side, price, size, fee_rate_bps) in fills {
+            let position = self
+                .positions
+                .entry(asset_id.clone())
+                .or_insert_with(|| Position {
+                    token_id: asset_id.clone(),
+                    market: trade.market.clone(),
+                    ..Default::default()
+                });
+            let fee = calculate_fee(fee_rate_bps.unwrap_or_default(), price, size);
+            position.apply_fill(side, price, size, fee);
+        }
+    }
+
+    /// Parses and applies the pages returned by `get_trades`.
+    pub fn ingest_pages(&mut self, pages: &[Value]) -> ClientResult<()> {
+        for page in pages {
+            let trades: Vec<Trade> =
+                serde_json::from_value(page.clone()).context("Failed to parse trades")?;
+            for trade in &trades {
+                self.ingest(trade);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn position(&self, token_id: &str) -> Option<&Position> {
+        self.positions.get(token_id)
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = &Position> {
+        self.positions.values()
+    }
+
+    pub fn set_mark(&mut self, token_id: &str, mark: Decimal) {
+        if let Some(position) = self.positions.get_mut(token_id) {
+            position.mark = Some(mark);
+        }
+    }
+
+    /// Marks every position to its midpoint.
+    pub async fn mark_to_market(&mut self, client: &ClobClient) -> ClientResult<()> {
+        let token_ids: Vec<_> = self.positions.keys().cloned().collect();
+        if token_ids.is_empty() {
+            return Ok(());
+        }
+        for (token_id, mid) in client.get_midpoints(&token_ids).await? {
+            self.set_mark(&token_id, mid);
+        }
+        Ok(())
+    }
+
+    pub fn realized_pnl(&self) -> Decimal {
+        self.positions.values().map(|p| p.realized_pnl).sum()
+    }
+
+    pub fn unrealized_pnl(&self) -> Decimal {
+        self.positions.values().map(Position::unrealized_pnl).sum()
+    }
+
+    pub fn fees(&self) -> Decimal {
+        self.positions.values().map(|p| p.fees).sum()
+    }
+
+    /// Compares the position in `token_id` with a `get_balance_allowance` response for
+    /// that conditional token.
+    pub fn check_balance(
+        &self,
+        token_id: &str,
+        balance_allowance: &Value,
+    ) -> ClientResult<Option<PositionMismatch>> {
+        let balance = balance_allowance["balance"]
+            .as_str()
+            .and_then(|b| Decimal::from_str(b).ok())
+            .with_context(|| format!("No balance in response: {balance_allowance}"))?;
+        let exchange = balance / Decimal::from(1_000_000);
+        let local = self
+            .positions
+            .get(token_id)
+            .map(|p| p.size)
+            .unwrap_or_default();
+        Ok((local != exchange).then(|| PositionMismatch {
+            token_id: token_id.to_owned(),
+            local,
+            exchange,
+        }))
+    }
+
+    /// Fetches the conditional token balance of every position and returns those that differ.
+    pub async fn reconcile(&self, client: &ClobClient) -> ClientResult<Vec<PositionMismatch>> {
+        let mut mismatches = Vec::new();
+        for token_id in self.positions.keys() {
+            let params = BalanceAllowanceParams {
+                asset_type: Some(AssetType::CONDITIONAL),
+                token_id: Some(token_id.clone()),
+                ..Default::default()
+            };
+            let response = client.get_balance_allowance(Some(params)).await?;
+            if let Some(mismatch) = self.check_balance(token_id, &response)? {
+                tracing::warn!(token_id = %token_id, local = %mismatch.local, exchange = %mismatch.exchange, "position mismatch");
+                mismatches.push(mismatch);
+            }
+        }
+        Ok(mismatches)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn trade(value: Value) -> Trade {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_portfolio_pnl_and_reconcile() {
+        let mut portfolio = Portfolio::new(["key"]);
+        let buy = trade(json!({
+            "id": "t1", "asset_id": "1", "market": "0xm", "side": "BUY", "size": "10",
+            "price": "0.4", "fee_rate_bps": "100", "status": "MATCHED", "trader_side": "TAKER",
+            "maker_orders": [{"order_id": "o", "owner": "other", "matched_amount": "10", "price": "0.4"}],
+        }));
+        portfolio.ingest(&buy);
+        portfolio.ingest(&buy);
+        // Someone else's buy fills 4 of our resting sell at 0.6.
+        portfolio.ingest(&trade(json!({
+            "id": "t2", "asset_id": "1", "market": "0xm", "side": "BUY", "size": "9",
+            "price": "0.6", "status": "MATCHED", "trader_side": "MAKER",
+            "maker_orders": [
+                {"order_id": "a", "owner": "other", "matched_amount": "5", "price": "0.6"},
+                {"order_id": "b", "owner": "key", "matched_amount": "4", "price": "0.6", "fee_rate_bps": "0"},
+            ],
+        })));
+
+        let position = portfolio.position("1").unwrap();
+        assert_eq!(position.size, Decimal::from(6));
+        assert_eq!(position.avg_cost, Decimal::new(4, 1));
+        assert_eq!(position.fees, Decimal::new(4, 2));
+        // 4 * (0.6 - 0.4) - 0.04 fees
+        assert_eq!(portfolio.realized_pnl(), Decimal::new(76, 2));
+
+        portfolio.set_mark("1", Decimal::new(5, 1));
+        assert_eq!(portfolio.unrealized_pnl(), Decimal::new(6, 1));
+
+        let ok = json!({"balance": "6000000", "allowance": "0"});
+        assert_eq!(portfolio.check_balance("1", &ok).unwrap(), None);
+        let off = json!({"balance": "5000000", "allowance": "0"});
+        assert_eq!(
+            portfolio
+                .check_balance("1", &off)
+                .unwrap()
+                .unwrap()
+                .exchange,
+            Decimal::from(5)
+        );
+    }
+
+    #[test]
+    fn test_maker_fill_on_complement_token() {
+        let mut portfolio = Portfolio::new(["key"]);
+        // Someone's YES buy at 0.4 is matched with our resting NO buy at 0.6.
+        portfolio.ingest(&trade(json!({
+            "id": "t1", "asset_id": "yes", "market": "0xm", "side": "BUY", "size": "10",
+            "price": "0.4", "status": "MATCHED", "trader_side": "MAKER",
+            "maker_orders": [{"order_id": "o", "owner": "key", "asset_id": "no",
+                "matched_amount": "10", "price": "0.6"}],
+        })));
+
+        assert!(portfolio.position("yes").is_none());
+        let position = portfolio.position("no").unwrap();
+        assert_eq!(position.size, Decimal::from(10));
+        assert_eq!(position.avg_cost, Decimal::new(6, 1));
+        assert_eq!(position.market, "0xm");
+    }
+
+    #[test]
+    fn test_position_flip() {
+        let mut position = Position::default();
+        position.apply_fill(
+            Side::BUY,
+            Decimal::new(5, 1),
+            Decimal::from(10),
+            Decimal::ZERO,
+        );
+        position.apply_fill(
+            Side::SELL,
+            Decimal::new(7, 1),
+            Decimal::from(15),
+            Decimal::ZERO,
+        );
+        assert_eq!(position.size, Decimal::from(-5));
+        assert_eq!(position.avg_cost, Decimal::new(7, 1));
+        assert_eq!(position.realized_pnl, Decimal::from(2));
+    }
+}
@@ -0,0 +1,157 @@
+use alloy_primitives::{Address, ChainId, Signature, B256};
+use alloy_signer::{Error, Result, Signer};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+/// Signer that delegates signing to an HTTP signing service.
+///
+/// Every hash is sent as `POST {url}` with a JSON body `{"address": "0x..", "hash": "0x.."}`
+/// and the service must respond with `{"signature": "0x.."}` (65 bytes, `r || s || v`).
+/// The returned signature is checked to recover to `address` before it is used.
+#[derive(Clone, Debug)]
+pub struct RemoteSigner {
+    http_client: Client,
+    url: String,
+    address: Address,
+    chain_id: Option<ChainId>,
+}
+
+#[derive(Serialize)]
+struct SignHashRequest {
+    address: Address,
+    hash: B256,
+}
+
+#[derive(Deserialize)]
+struct SignHashResponse {
+    signature: String,
+}
+
+impl RemoteSigner {
+    pub fn new(url: &str, address: Address) -> Self {
+        Self {
+            http_client: Client::new(),
+            url: url.to_owned(),
+            address,
+            chain_id: None,
+        }
+    }
+
+    pub fn with_http_client(mut self, http_client: Client) -> Self {
+        self.http_client = http_client;
+        self
+    }
+}
+
+#[async_trait]
+impl Signer for RemoteSigner {
+    async fn sign_hash(&self, hash: &B256) -> Result<Signature> {
+        let resp = self
+            .http_client
+            .post(&self.url)
+            .json(&SignHashRequest {
+                address: self.address,
+                hash: *hash,
+            })
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(Error::other)?
+            .json::<SignHashResponse>()
+            .await
+            .map_err(Error::other)?;
+
+        let signature = resp.signature.parse::<Signature>()?;
+        let recovered = signature.recover_address_from_prehash(hash)?;
+        if recovered != self.address {
+            return Err(Error::message(format!(
+                "Remote signer returned a signature for {recovered}, expected {}",
+                self.address
+            )));
+        }
+        Ok(signature)
+    }
+
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    fn chain_id(&self) -> Option<ChainId> {
+        self.chain_id
+    }
+
+    fn set_chain_id(&mut self, chain_id: Option<ChainId>) {
+        self.chain_id = chain_id;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::hex::encode_prefixed;
+    use alloy_signer::SignerSync;
+    use alloy_signer_local::PrivateKeySigner;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    // Single-request stand-in for a signing service, backed by a local key.
+    async fn serve_once(listener: TcpListener, key: PrivateKeySigner) {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut buf = Vec::new();
+        let body = loop {
+            let mut chunk = [0u8; 1024];
+            let n = stream.read(&mut chunk).await.unwrap();
+            buf.extend_from_slice(&chunk[..n]);
+            let text = String::from_utf8_lossy(&buf);
+            if let Some(idx) = text.find("\r\n\r\n") {
+                let len = text
+                    .lines()
+                    .find_map(|l| {
+                        l.to_lowercase()
+                            .strip_prefix("content-length:")
+                            .map(|v| v.trim().parse::<usize>().unwrap())
+                    })
+                    .unwrap_or(0);
+                if buf.len() >= idx + 4 + len {
+                    break buf[idx + 4..idx + 4 + len].to_vec();
+                }
+            }
+        };
+
+        let req: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let hash: B256 = req["hash"].as_str().unwrap().parse().unwrap();
+        let sig = key.sign_hash_sync(&hash).unwrap();
+        let resp = serde_json::json!({ "signature": encode_prefixed(sig.as_bytes()) }).to_string();
+        let out = format!(
+            "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{resp}",
+            resp.len()
+        );
+        stream.write_all(out.as_bytes()).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_remote_signer_matches_local_signature() {
+        let key = PrivateKeySigner::random();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/sign", listener.local_addr().unwrap());
+        tokio::spawn(serve_once(listener, key.clone()));
+
+        let remote = RemoteSigner::new(&url, key.address());
+        let hash = B256::repeat_byte(7);
+        let sig = remote.sign_hash(&hash).await.unwrap();
+
+        assert_eq!(sig, key.sign_hash_sync(&hash).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_remote_signer_rejects_wrong_key() {
+        let key = PrivateKeySigner::random();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/sign", listener.local_addr().unwrap());
+        tokio::spawn(serve_once(listener, key));
+
+        let remote = RemoteSigner::new(&url, PrivateKeySigner::random().address());
+        assert!(remote.sign_hash(&B256::repeat_byte(7)).await.is_err());
+    }
+}
@@ -0,0 +1,528 @@
+//! Pre-trade risk checks run before an order is signed.
+
+use crate::utils::get_current_unix_time_secs;
+use crate::{OrderManager, Portfolio, Side, Value};
+use rust_decimal::Decimal;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Mutex, MutexGuard};
+
+/// Limits enforced by a [`RiskGuard`]. `None` disables a check.
+#[derive(Debug, Clone, Default)]
+pub struct RiskLimits {
+    /// Maximum `price * size` of a single order.
+    pub max_order_notional: Option<Decimal>,
+    /// Maximum absolute position in one token after the order and every open order on
+    /// the same side fill.
+    pub max_position_per_token: Option<Decimal>,
+    /// Maximum sum of absolute positions across the tokens of one market.
+    pub max_position_per_market: Option<Decimal>,
+    pub max_open_orders: Option<usize>,
+    /// Maximum distance between the order price and the midpoint.
+    pub price_collar: Option<Decimal>,
+    /// Loss since the start of the UTC day at which new orders are refused.
+    pub max_daily_loss: Option<Decimal>,
+}
+
+/// Why an order was refused. Returned inside the `anyhow::Error` from `create_order`,
+/// so callers can `downcast_ref::<RiskViolation>()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RiskViolation {
+    OrderNotional {
+        notional: Decimal,
+        limit: Decimal,
+    },
+    TokenPosition {
+        token_id: String,
+        position: Decimal,
+        limit: Decimal,
+    },
+    MarketPosition {
+        market: String,
+        position: Decimal,
+        limit: Decimal,
+    },
+    OpenOrders {
+        open: usize,
+        limit: usize,
+    },
+    PriceCollar {
+        price: Decimal,
+        midpoint: Decimal,
+        collar: Decimal,
+    },
+    DailyLoss {
+        loss: Decimal,
+        limit: Decimal,
+    },
+}
+
+impl fmt::Display for RiskViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RiskViolation::OrderNotional { notional, limit } => {
+                write!(f, "order notional {notional} exceeds limit {limit}")
+            }
+            RiskViolation::TokenPosition {
+                token_id,
+                position,
+                limit,
+            } => write!(
+                f,
+                "position {position} in token {token_id} would exceed limit {limit}"
+            ),
+            RiskViolation::MarketPosition {
+                market,
+                position,
+                limit,
+            } => write!(
+                f,
+                "position {position} in market {market} would exceed limit {limit}"
+            ),
+            RiskViolation::OpenOrders { open, limit } => {
+                write!(f, "{open} open orders, limit is {limit}")
+            }
+            RiskViolation::PriceCollar {
+                price,
+                midpoint,
+                collar,
+            } => write!(
+                f,
+                "price {price} is more than {collar} away from midpoint {midpoint}"
+            ),
+            RiskViolation::DailyLoss { loss, limit } => {
+                write!(f, "daily loss {loss} reached limit {limit}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RiskViolation {}
+
+/// Unfilled size of a resting order.
+struct OpenExposure {
+    token_id: String,
+    side: Side,
+    remaining: Decimal,
+}
+
+#[derive(Default)]
+struct RiskState {
+    /// token_id -> (market, position)
+    positions: HashMap<String, (Option<String>, Decimal)>,
+    open_orders: usize,
+    /// order ID -> exposure
+    exposures: HashMap<String, OpenExposure>,
+    day: u64,
+    day_start_pnl: Option<Decimal>,
+    pnl: Decimal,
+}
+
+impl RiskState {
+    /// Unfilled size of open orders in `token_id` on `side`.
+    fn open_size(&self, token_id: &str, side: Side) -> Decimal {
+        self.exposures
+            .values()
+            .filter(|e| e.token_id == token_id && e.side == side)
+            .map(|e| e.remaining)
+            .sum()
+    }
+
+    /// Position in `token_id` if every open order on `side` filled.
+    fn worst_case(&self, token_id: &str, current: Decimal, side: Side) -> Decimal {
+        match side {
+            Side::BUY => current + self.open_size(token_id, side),
+            Side::SELL => current - self.open_size(token_id, side),
+        }
+    }
+
+    fn roll_day(&mut self, now: u64) {
+        let day = now / 86_400;
+        if self.day != day || self.day_start_pnl.is_none() {
+            self.day = day;
+            self.day_start_pnl = Some(self.pnl);
+        }
+    }
+}
+
+/// Checks orders against [`RiskLimits`] using positions, open orders and PnL fed to it by
+/// the caller. Install it with [`ClobClient::set_risk_guard`](crate::ClobClient::set_risk_guard)
+/// and keep a handle to update its state.
+pub struct RiskGuard {
+    limits: RiskLimits,
+    state: Mutex<RiskState>,
+}
+
+impl RiskGuard {
+    pub fn new(limits: RiskLimits) -> Self {
+        Self {
+            limits,
+            state: Mutex::default(),
+        }
+    }
+
+    pub fn limits(&self) -> &RiskLimits {
+        &self.limits
+    }
+
+    pub fn set_position(&self, token_id: &str, market: Option<&str>, size: Decimal) {
+        let mut state = self.state();
+        let entry = state.positions.entry(token_id.to_owned()).or_default();
+        if market.is_some() {
+            entry.0 = market.map(str::to_owned);
+        }
+        entry.1 = size;
+    }
+
+    /// Associates `token_id` with its market for the per-market limit.
+    pub fn set_market(&self, token_id: &str, market: &str) {
+        let mut state = self.state();
+        state.positions.entry(token_id.to_owned()).or_default().0 = Some(market.to_owned());
+    }
+
+    pub fn market_of(&self, token_id: &str) -> Option<String> {
+        self.state()
+            .positions
+            .get(token_id)
+            .and_then(|(market, _)| market.clone())
+    }
+
+    pub fn set_open_orders(&self, open_orders: usize) {
+        self.state().open_orders = open_orders;
+    }
+
+    /// Sets total PnL (realized plus unrealized). The first value seen each UTC day is the
+    /// baseline the daily loss is measured from.
+    pub fn set_pnl(&self, pnl: Decimal) {
+        let mut state = self.state();
+        state.pnl = pnl;
+        state.roll_day(get_current_unix_time_secs());
+    }
+
+    /// Copies positions and PnL from a [`Portfolio`].
+    pub fn sync_portfolio(&self, portfolio: &Portfolio) {
+        for position in portfolio.positions() {
+            self.set_position(&position.token_id, Some(&position.market), position.size);
+        }
+        self.set_pnl(portfolio.realized_pnl() + portfolio.unrealized_pnl());
+    }
+
+    /// Counts the unfilled `size` of a resting order towards the position limits until it
+    /// is cancelled or filled. [`ClobClient`](crate::ClobClient) calls this for orders it
+    /// posts while the guard is installed.
+    pub fn add_open_order(&self, order_id: &str, token_id: &str, side: Side, size: Decimal) {
+        self.state().exposures.insert(
+            order_id.to_owned(),
+            OpenExposure {
+                token_id: token_id.to_owned(),
+                side,
+                remaining: size,
+            },
+        );
+    }
+
+    pub fn remove_open_order(&self, order_id: &str) {
+        self.state().exposures.remove(order_id);
+    }
+
+    /// Moves `size` filled shares of `order_id` out of its open exposure. The fill itself
+    /// counts once the position is updated.
+    pub fn fill_open_order(&self, order_id: &str, size: Decimal) {
+        let mut state = self.state();
+        if let Some(exposure) = state.exposures.get_mut(order_id) {
+            exposure.remaining -= size;
+            if exposure.remaining <= Decimal::ZERO {
+                state.exposures.remove(order_id);
+            }
+        }
+    }
+
+    /// Records the order a successful `post_order` response accepted for `size` shares of
+    /// `token_id`, less what filled on arrival.
+    pub fn on_post_response(&self, token_id: &str, side: Side, size: Decimal, response: &Value) {
+        let Some(order_id) = response["orderID"].as_str().filter(|id| !id.is_empty()) else {
+            return;
+        };
+        if response["success"] == false {
+            return;
+        }
+        match response["status"].as_str().unwrap_or_default() {
+            "live" | "delayed" => {
+                // Shares filled on arrival: the taking side of a buy, the making side of a sell.
+                let filled = match side {
+                    Side::BUY => &response["takingAmount"],
+                    Side::SELL => &response["makingAmount"],
+                }
+                .as_str()
+                .and_then(|a| Decimal::from_str(a).ok())
+                .unwrap_or_default();
+                if filled < size {
+                    self.add_open_order(order_id, token_id, side, size - filled);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Drops the orders listed as `canceled` in a cancel response.
+    pub fn on_cancel_response(&self, response: &Value) {
+        let mut state = self.state();
+        for id in response["canceled"].as_array().into_iter().flatten() {
+            if let Some(id) = id.as_str() {
+                state.exposures.remove(id);
+            }
+        }
+    }
+
+    /// Copies the number of open orders and their unfilled sizes from an [`OrderManager`].
+    pub fn sync_orders(&self, orders: &OrderManager) {
+        let open = orders.all_open_orders();
+        let mut state = self.state();
+        state.open_orders = open.len();
+        state.exposures = open
+            .into_iter()
+            .map(|o| {
+                let id = o
+                    .order_id
+                    .clone()
+                    .unwrap_or_else(|| format!("local-{}", o.local_id));
+                let exposure = OpenExposure {
+                    remaining: o.remaining(),
+                    token_id: o.token_id,
+                    side: o.side,
+                };
+                (id, exposure)
+            })
+            .collect();
+    }
+
+    /// Checks an order for `size` shares of `token_id` at `price`. `midpoint` is only used
+    /// for the price collar, which is skipped without it.
+    pub fn check(
+        &self,
+        token_id: &str,
+        side: Side,
+        price: Decimal,
+        size: Decimal,
+        midpoint: Option<Decimal>,
+    ) -> Result<(), RiskViolation> {
+        let limits = &self.limits;
+        let mut state = self.state();
+        state.roll_day(get_current_unix_time_secs());
+
+        if let Some(limit) = limits.max_daily_loss {
+            let loss = state.day_start_pnl.unwrap_or_default() - state.pnl;
+            if loss >= limit {
+                return Err(RiskViolation::DailyLoss { loss, limit });
+            }
+        }
+        if let Some(limit) = limits.max_open_orders {
+            if state.open_orders >= limit {
+                return Err(RiskViolation::OpenOrders {
+                    open: state.open_orders,
+                    limit,
+                });
+            }
+        }
+        if let Some(limit) = limits.max_order_notional {
+            let notional = price * size;
+            if notional > limit {
+                return Err(RiskViolation::OrderNotional { notional, limit });
+            }
+        }
+        if let (Some(collar), Some(midpoint)) = (limits.price_collar, midpoint) {
+            if (price - midpoint).abs() > collar {
+                return Err(RiskViolation::PriceCollar {
+                    price,
+                    midpoint,
+                    collar,
+                });
+            }
+        }
+
+        // Open orders on the same side count as if they filled. Orders that shrink a
+        // position are always allowed through the position limits.
+        let (market, current) = state.positions.get(token_id).cloned().unwrap_or_default();
+        let after = match side {
+            Side::BUY => state.worst_case(token_id, current, side) + size,
+            Side::SELL => state.worst_case(token_id, current, side) - size,
+        };
+        if after.abs() <= current.abs() {
+            return Ok(());
+        }
+        if let Some(limit) = limits.max_position_per_token {
+            if after.abs() > limit {
+                return Err(RiskViolation::TokenPosition {
+                    token_id: token_id.to_owned(),
+                    position: after,
+                    limit,
+                });
+            }
+        }
+        if let (Some(limit), Some(market)) = (limits.max_position_per_market, market) {
+            let tokens: HashSet<&str> = state
+                .positions
+                .iter()
+                .filter(|(_, (m, _))| m.as_ref() == Some(&market))
+                .map(|(t, _)| t.as_str())
+                .filter(|t| *t != token_id)
+                .collect();
+            let others: Decimal = tokens
+                .into_iter()
+                .map(|t| {
+                    let current = state.positions[t].1;
+                    let long = state.worst_case(t, current, Side::BUY).abs();
+                    let short = state.worst_case(t, current, Side::SELL).abs();
+                    long.max(short)
+                })
+                .sum();
+            let position = others + after.abs();
+            if position > limit {
+                return Err(RiskViolation::MarketPosition {
+                    market,
+                    position,
+                    limit,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn state(&self) -> MutexGuard<'_, RiskState> {
+        self.state.lock().expect("Risk guard lock poisoned")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_risk_checks() {
+        let guard = RiskGuard::new(RiskLimits {
+            max_order_notional: Some(Decimal::from(50)),
+            max_position_per_token: Some(Decimal::from(100)),
+            max_position_per_market: Some(Decimal::from(150)),
+            max_open_orders: Some(2),
+            price_collar: Some(Decimal::new(5, 2)),
+            max_daily_loss: Some(Decimal::from(20)),
+        });
+        let half = Decimal::new(5, 1);
+        guard.set_position("yes", Some("0xm"), Decimal::from(90));
+        guard.set_position("no", Some("0xm"), Decimal::from(40));
+        guard.set_pnl(Decimal::from(10));
+
+        assert!(matches!(
+            guard.check("yes", Side::BUY, half, Decimal::from(200), None),
+            Err(RiskViolation::OrderNotional { .. })
+        ));
+        assert!(matches!(
+            guard.check("yes", Side::BUY, half, Decimal::from(20), None),
+            Err(RiskViolation::TokenPosition { .. })
+        ));
+        assert!(guard
+            .check("yes", Side::SELL, half, Decimal::from(20), None)
+            .is_ok());
+        assert_eq!(
+            guard.check("no", Side::BUY, half, Decimal::from(30), None),
+            Err(RiskViolation::MarketPosition {
+                market: "0xm".into(),
+                position: Decimal::from(160),
+                limit: Decimal::from(150),
+            })
+        );
+        assert!(matches!(
+            guard.check(
+                "new",
+                Side::BUY,
+                half,
+                Decimal::ONE,
+                Some(Decimal::new(6, 1))
+            ),
+            Err(RiskViolation::PriceCollar { .. })
+        ));
+
+        guard.set_open_orders(2);
+        assert!(matches!(
+            guard.check("new", Side::BUY, half, Decimal::ONE, None),
+            Err(RiskViolation::OpenOrders { .. })
+        ));
+        guard.set_open_orders(0);
+
+        // Resting buys count towards the limits until they are cancelled or filled.
+        guard.add_open_order("o1", "yes", Side::BUY, Decimal::from(8));
+        assert!(guard
+            .check("yes", Side::BUY, half, Decimal::from(2), None)
+            .is_ok());
+        guard.add_open_order("o2", "yes", Side::BUY, Decimal::from(2));
+        assert_eq!(
+            guard.check("yes", Side::BUY, half, Decimal::from(2), None),
+            Err(RiskViolation::TokenPosition {
+                token_id: "yes".into(),
+                position: Decimal::from(102),
+                limit: Decimal::from(100),
+            })
+        );
+        assert!(matches!(
+            guard.check("no", Side::BUY, half, Decimal::from(15), None),
+            Err(RiskViolation::MarketPosition { .. })
+        ));
+        guard.fill_open_order("o1", Decimal::from(8));
+        guard.on_cancel_response(&serde_json::json!({"canceled": ["o2"], "not_canceled": {}}));
+        assert!(guard
+            .check("yes", Side::BUY, half, Decimal::from(2), None)
+            .is_ok());
+
+        guard.set_pnl(Decimal::from(-10));
+        let err = guard
+            .check("new", Side::BUY, half, Decimal::ONE, None)
+            .unwrap_err();
+        assert_eq!(err.to_string(), "daily loss 20 reached limit 20");
+    }
+
+    #[test]
+    fn test_open_exposure_from_responses() {
+        let guard = RiskGuard::new(RiskLimits {
+            max_position_per_token: Some(Decimal::from(10)),
+            ..Default::default()
+        });
+        let half = Decimal::new(5, 1);
+        // A buy of 10 that filled 4 on arrival rests with 6; the fill counts once the
+        // position is synced.
+        guard.on_post_response(
+            "yes",
+            Side::BUY,
+            Decimal::from(10),
+            &serde_json::json!({
+                "success": true,
+                "orderID": "o1",
+                "status": "live",
+                "makingAmount": "2",
+                "takingAmount": "4",
+            }),
+        );
+        guard.set_position("yes", None, Decimal::from(4));
+        assert!(guard
+            .check("yes", Side::BUY, half, Decimal::ONE, None)
+            .is_err());
+        assert!(guard
+            .check("yes", Side::SELL, half, Decimal::ONE, None)
+            .is_ok());
+        // Fully matched orders leave nothing resting.
+        guard.on_post_response(
+            "no",
+            Side::BUY,
+            Decimal::from(10),
+            &serde_json::json!({"success": true, "orderID": "o2", "status": "matched"}),
+        );
+        assert!(guard
+            .check("no", Side::BUY, half, Decimal::from(10), None)
+            .is_ok());
+
+        guard.on_cancel_response(&serde_json::json!({"canceled": ["o1"], "not_canceled": {}}));
+        assert!(guard
+            .check("yes", Side::BUY, half, Decimal::from(6), None)
+            .is_ok());
+    }
+}
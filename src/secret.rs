@@ -0,0 +1,74 @@
+use serde::{Deserialize, Deserializer};
+use std::fmt;
+use zeroize::Zeroizing;
+
+/// A string holding key material.
+///
+/// The contents are zeroed on drop and never printed by `Debug`; use
+/// [`expose_secret`](Secret::expose_secret) where the raw value is needed.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Secret(Zeroizing<String>);
+
+impl Secret {
+    pub fn new(secret: impl Into<String>) -> Self {
+        Secret(Zeroizing::new(secret.into()))
+    }
+
+    pub fn expose_secret(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Secret(***)")
+    }
+}
+
+impl From<String> for Secret {
+    fn from(secret: String) -> Self {
+        Secret::new(secret)
+    }
+}
+
+impl From<&str> for Secret {
+    fn from(secret: &str) -> Self {
+        Secret::new(secret)
+    }
+}
+
+impl<'de> Deserialize<'de> for Secret {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Secret::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ApiCreds;
+
+    #[test]
+    fn test_api_creds_debug_is_redacted() {
+        let creds: ApiCreds = serde_json::from_str(
+            r#"{"apiKey": "key-id", "secret": "c2VjcmV0", "passphrase": "hunter2"}"#,
+        )
+        .unwrap();
+        let debug = format!("{creds:?}");
+
+        assert!(debug.contains("key-id"));
+        assert!(!debug.contains("c2VjcmV0"));
+        assert!(!debug.contains("hunter2"));
+        assert_eq!(creds.passphrase.expose_secret(), "hunter2");
+    }
+
+    #[test]
+    fn test_api_creds_expose_secrets_round_trip() {
+        let creds = ApiCreds::new("key-id", "c2VjcmV0", "hunter2");
+        let json = serde_json::to_string(&creds.expose_secrets()).unwrap();
+        let parsed: ApiCreds = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(parsed.api_key, "key-id");
+        assert_eq!(parsed.secret, creds.secret);
+        assert_eq!(parsed.passphrase.expose_secret(), "hunter2");
+    }
+}
@@ -5,6 +5,7 @@ use std::time::{SystemTime, UNIX_EPOCH};
 
 use hmac::{Hmac, Mac};
 use sha2::Sha256;
+use zeroize::Zeroizing;
 
 type HmacSha256 = Hmac<Sha256>;
 
@@ -15,6 +16,13 @@ pub fn get_current_unix_time_secs() -> u64 {
         .as_secs()
 }
 
+pub fn get_current_unix_time_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_millis() as u64
+}
+
 pub fn build_hmac_signature<T>(
     secret: &str,
     timestamp: u64,
@@ -25,20 +33,33 @@ pub fn build_hmac_signature<T>(
 where
     T: ?Sized + Serialize,
 {
-    let decoded = URL_SAFE
-        .decode(secret)
-        .context("Can't decode secret to base64")?;
+    let body = body
+        .map(|s| serde_json::to_string(s).context("Failed to serialize body to JSON"))
+        .transpose()?;
+    build_hmac_signature_raw(secret, timestamp, method, req_path, body.as_deref())
+}
+
+/// Same as [`build_hmac_signature`] for a body that is already serialized to JSON.
+pub fn build_hmac_signature_raw(
+    secret: &str,
+    timestamp: u64,
+    method: &str,
+    req_path: &str,
+    body: Option<&str>,
+) -> Result<String> {
+    let decoded = Zeroizing::new(
+        URL_SAFE
+            .decode(secret)
+            .context("Can't decode secret to base64")?,
+    );
     let message = match body {
         None => format!("{timestamp}{method}{req_path}"),
-        Some(s) => {
-            let json_str = serde_json::to_string(s)
-                .context("Failed to serialize body to JSON")?;
-            
+        Some(json_str) => {
             // Replace single quotes with double quotes to match Python's str(body).replace("'", '"')
             // Note: This is mainly for compatibility, as proper JSON shouldn't have single quotes
             let body_str = json_str.replace('\'', "\"");
-            
-           format!("{}{}{}{}", timestamp, method, req_path, body_str)
+
+            format!("{}{}{}{}", timestamp, method, req_path, body_str)
         }
     };
 
@@ -0,0 +1,414 @@
+//! Checks run on order terms before an order is signed.
+
+use crate::{Expiry, Market, MarketOrderQuote, Side, TimeInForce};
+use rust_decimal::Decimal;
+use std::fmt;
+
+/// How far in the future a GTD expiration has to be. The exchange rejects orders that
+/// expire within a minute of being received.
+pub const GTD_EXPIRATION_BUFFER_SECS: u64 = 60;
+
+/// The expiration to sign for a GTD order ending at `expiry`, with the exchange's time
+/// at `now`.
+pub(crate) fn gtd_expiration(expiry: Expiry, now: u64) -> u64 {
+    let at = match expiry {
+        Expiry::At(at) => at,
+        Expiry::After(duration) => now + duration.as_secs(),
+    };
+    at + GTD_EXPIRATION_BUFFER_SECS
+}
+
+/// Why an order failed validation. Returned inside the `anyhow::Error` from
+/// `create_order`, so callers can `downcast_ref::<OrderValidationError>()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OrderValidationError {
+    PriceOutOfRange {
+        price: Decimal,
+        min: Decimal,
+        max: Decimal,
+    },
+    PriceNotOnTick {
+        price: Decimal,
+        tick_size: Decimal,
+    },
+    SizeBelowMinimum {
+        size: Decimal,
+        minimum: Decimal,
+    },
+    ExpirationTooSoon {
+        expiration: u64,
+        earliest: u64,
+    },
+    MarketNotActive {
+        condition_id: String,
+    },
+    MarketClosed {
+        condition_id: String,
+    },
+    FeeRateMismatch {
+        fee_rate_bps: u32,
+        expected: u32,
+    },
+    /// A post-only order would trade against the best opposite price.
+    WouldCross {
+        price: Decimal,
+        best: Decimal,
+    },
+    /// Post-only orders have to be able to rest.
+    PostOnlyNotResting {
+        time_in_force: TimeInForce,
+    },
+    /// The book holds less than the USDC a market order wants to spend.
+    InsufficientLiquidity {
+        amount: Decimal,
+        available: Decimal,
+    },
+    /// A market order would fill beyond its worst price or slippage limit.
+    SlippageExceeded {
+        worst_price: Decimal,
+        limit: Decimal,
+    },
+}
+
+impl fmt::Display for OrderValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OrderValidationError::PriceOutOfRange { price, min, max } => {
+                write!(f, "price {price} is outside [{min}, {max}]")
+            }
+            OrderValidationError::PriceNotOnTick { price, tick_size } => {
+                write!(
+                    f,
+                    "price {price} is not a multiple of tick size {tick_size}"
+                )
+            }
+            OrderValidationError::SizeBelowMinimum { size, minimum } => {
+                write!(f, "size {size} is below the minimum order size {minimum}")
+            }
+            OrderValidationError::ExpirationTooSoon {
+                expiration,
+                earliest,
+            } => write!(f, "expiration {expiration} is before {earliest}"),
+            OrderValidationError::MarketNotActive { condition_id } => {
+                write!(f, "market {condition_id} is not active")
+            }
+            OrderValidationError::MarketClosed { condition_id } => {
+                write!(f, "market {condition_id} is closed")
+            }
+            OrderValidationError::FeeRateMismatch {
+                fee_rate_bps,
+                expected,
+            } => write!(
+                f,
+                "fee rate {fee_rate_bps} bps does not match the market fee rate {expected} bps"
+            ),
+            OrderValidationError::WouldCross { price, best } => {
+                write!(
+                    f,
+                    "post-only order at {price} would cross the book at {best}"
+                )
+            }
+            OrderValidationError::PostOnlyNotResting { time_in_force } => {
+                write!(f, "post-only orders cannot be {time_in_force:?}")
+            }
+            OrderValidationError::InsufficientLiquidity { amount, available } => write!(
+                f,
+                "not enough liquidity to spend {amount}, the book holds {available}"
+            ),
+            OrderValidationError::SlippageExceeded { worst_price, limit } => {
+                write!(
+                    f,
+                    "market order would fill at {worst_price}, limit is {limit}"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for OrderValidationError {}
+
+/// The terms of an order together with what the exchange requires of it.
+pub(crate) struct OrderValidation<'a> {
+    pub price: Decimal,
+    pub size: Decimal,
+    pub tick_size: Decimal,
+    /// Unix seconds, 0 for orders that do not expire.
+    pub expiration: u64,
+    pub fee_rate_bps: u32,
+    pub market_fee_rate_bps: u32,
+    pub market: &'a Market,
+    pub now: u64,
+}
+
+impl OrderValidation<'_> {
+    pub fn validate(&self) -> Result<(), OrderValidationError> {
+        let market = self.market;
+        if market.closed {
+            return Err(OrderValidationError::MarketClosed {
+                condition_id: market.condition_id.clone(),
+            });
+        }
+        if !market.active {
+            return Err(OrderValidationError::MarketNotActive {
+                condition_id: market.condition_id.clone(),
+            });
+        }
+
+        let (min, max) = (self.tick_size, Decimal::ONE - self.tick_size);
+        if self.price < min || self.price > max {
+            return Err(OrderValidationError::PriceOutOfRange {
+                price: self.price,
+                min,
+                max,
+            });
+        }
+        if !(self.price % self.tick_size).is_zero() {
+            return Err(OrderValidationError::PriceNotOnTick {
+                price: self.price,
+                tick_size: self.tick_size,
+            });
+        }
+        if self.size < market.minimum_order_size {
+            return Err(OrderValidationError::SizeBelowMinimum {
+                size: self.size,
+                minimum: market.minimum_order_size,
+            });
+        }
+
+        let earliest = self.now + GTD_EXPIRATION_BUFFER_SECS;
+        if self.expiration != 0 && self.expiration < earliest {
+            return Err(OrderValidationError::ExpirationTooSoon {
+                expiration: self.expiration,
+                earliest,
+            });
+        }
+        if self.fee_rate_bps != self.market_fee_rate_bps {
+            return Err(OrderValidationError::FeeRateMismatch {
+                fee_rate_bps: self.fee_rate_bps,
+                expected: self.market_fee_rate_bps,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Refuses a post-only order with a time in force that never rests on the book.
+pub(crate) fn check_post_only_resting(
+    time_in_force: TimeInForce,
+) -> Result<(), OrderValidationError> {
+    match time_in_force {
+        TimeInForce::FOK | TimeInForce::FAK => {
+            Err(OrderValidationError::PostOnlyNotResting { time_in_force })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Refuses a post-only order that would take liquidity at the given top of book.
+pub(crate) fn check_post_only(
+    side: Side,
+    price: Decimal,
+    best_bid: Option<Decimal>,
+    best_ask: Option<Decimal>,
+) -> Result<(), OrderValidationError> {
+    let crossed = match side {
+        Side::BUY => best_ask.filter(|ask| price >= *ask),
+        Side::SELL => best_bid.filter(|bid| price <= *bid),
+    };
+    match crossed {
+        Some(best) => Err(OrderValidationError::WouldCross { price, best }),
+        None => Ok(()),
+    }
+}
+
+/// Refuses a market buy that would fill above `worst_price` or more than `max_slippage`
+/// above the best ask.
+pub(crate) fn check_slippage(
+    quote: &MarketOrderQuote,
+    max_slippage: Option<Decimal>,
+    worst_price: Option<Decimal>,
+) -> Result<(), OrderValidationError> {
+    let limit = [worst_price, max_slippage.map(|s| quote.best_price + s)]
+        .into_iter()
+        .flatten()
+        .min();
+    match limit {
+        Some(limit) if quote.worst_price > limit => Err(OrderValidationError::SlippageExceeded {
+            worst_price: quote.worst_price,
+            limit,
+        }),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::time::Duration;
+
+    fn market(active: bool, closed: bool) -> Market {
+        serde_json::from_value(json!({
+            "condition_id": "0xm",
+            "tokens": [{ "token_id": "1", "outcome": "Yes" }, { "token_id": "2", "outcome": "No" }],
+            "rewards": { "rates": null, "min_size": 0, "max_spread": 0 },
+            "min_incentive_size": null,
+            "max_incentive_spread": null,
+            "active": active,
+            "closed": closed,
+            "question_id": "",
+            "minimum_order_size": 15,
+            "minimum_tick_size": 0.01,
+            "description": "",
+            "question": "",
+            "market_slug": "",
+            "seconds_delay": 0,
+            "icon": "",
+            "fpmm": "",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_order_validation() {
+        let open = market(true, false);
+        let order = OrderValidation {
+            price: Decimal::new(5, 1),
+            size: Decimal::from(20),
+            tick_size: Decimal::new(1, 2),
+            expiration: 0,
+            fee_rate_bps: 1000,
+            market_fee_rate_bps: 1000,
+            market: &open,
+            now: 1_000,
+        };
+        assert_eq!(order.validate(), Ok(()));
+
+        let check = |order: OrderValidation| order.validate().unwrap_err();
+        assert!(matches!(
+            check(OrderValidation {
+                price: Decimal::new(505, 3),
+                ..order
+            }),
+            OrderValidationError::PriceNotOnTick { .. }
+        ));
+        assert!(matches!(
+            check(OrderValidation {
+                price: Decimal::new(995, 3),
+                ..order
+            }),
+            OrderValidationError::PriceOutOfRange { .. }
+        ));
+        assert!(matches!(
+            check(OrderValidation {
+                size: Decimal::from(10),
+                ..order
+            }),
+            OrderValidationError::SizeBelowMinimum { .. }
+        ));
+        assert_eq!(
+            check(OrderValidation {
+                expiration: 1_030,
+                ..order
+            }),
+            OrderValidationError::ExpirationTooSoon {
+                expiration: 1_030,
+                earliest: 1_060,
+            }
+        );
+        assert_eq!(
+            OrderValidation {
+                expiration: 1_060,
+                ..order
+            }
+            .validate(),
+            Ok(())
+        );
+        assert!(matches!(
+            check(OrderValidation {
+                fee_rate_bps: 0,
+                ..order
+            }),
+            OrderValidationError::FeeRateMismatch { .. }
+        ));
+
+        let inactive = market(false, false);
+        assert!(matches!(
+            check(OrderValidation {
+                market: &inactive,
+                ..order
+            }),
+            OrderValidationError::MarketNotActive { .. }
+        ));
+        let closed = market(true, true);
+        assert!(matches!(
+            check(OrderValidation {
+                market: &closed,
+                ..order
+            }),
+            OrderValidationError::MarketClosed { .. }
+        ));
+    }
+
+    #[test]
+    fn test_gtd_expiration() {
+        assert_eq!(gtd_expiration(Expiry::At(2_000), 1_000), 2_060);
+        assert_eq!(
+            gtd_expiration(Expiry::After(Duration::from_secs(120)), 1_000),
+            1_180
+        );
+    }
+
+    #[test]
+    fn test_post_only() {
+        let (bid, ask) = (Some(Decimal::new(4, 1)), Some(Decimal::new(6, 1)));
+        let price = |p| Decimal::new(p, 2);
+
+        assert_eq!(check_post_only(Side::BUY, price(59), bid, ask), Ok(()));
+        assert_eq!(
+            check_post_only(Side::BUY, price(60), bid, ask),
+            Err(OrderValidationError::WouldCross {
+                price: price(60),
+                best: price(60),
+            })
+        );
+        assert_eq!(check_post_only(Side::SELL, price(41), bid, ask), Ok(()));
+        assert!(check_post_only(Side::SELL, price(35), bid, ask).is_err());
+        // An empty side cannot be crossed.
+        assert_eq!(check_post_only(Side::BUY, price(99), bid, None), Ok(()));
+
+        assert_eq!(check_post_only_resting(TimeInForce::GTC), Ok(()));
+        assert_eq!(
+            check_post_only_resting(TimeInForce::FAK),
+            Err(OrderValidationError::PostOnlyNotResting {
+                time_in_force: TimeInForce::FAK
+            })
+        );
+    }
+
+    #[test]
+    fn test_slippage() {
+        let quote = MarketOrderQuote {
+            best_price: Decimal::new(5, 1),
+            worst_price: Decimal::new(6, 1),
+            average_price: Decimal::new(55, 2),
+            size: Decimal::from(20),
+            amount: Decimal::from(11),
+            levels: Vec::new(),
+        };
+        assert_eq!(check_slippage(&quote, None, None), Ok(()));
+        assert_eq!(
+            check_slippage(&quote, Some(Decimal::new(1, 1)), None),
+            Ok(())
+        );
+        // The tighter of the two limits applies.
+        assert_eq!(
+            check_slippage(&quote, Some(Decimal::new(1, 1)), Some(Decimal::new(55, 2))),
+            Err(OrderValidationError::SlippageExceeded {
+                worst_price: Decimal::new(6, 1),
+                limit: Decimal::new(55, 2),
+            })
+        );
+        assert!(check_slippage(&quote, Some(Decimal::new(5, 2)), None).is_err());
+    }
+}
@@ -0,0 +1,149 @@
+use crate::{
+    ClientResult, ClobClient, OpenOrder, OpenOrderParams, OrderType, SignedOrderRequest,
+    TradeParams, Value,
+};
+use async_trait::async_trait;
+use std::sync::Arc;
+
+/// The order placement and query surface shared by [`ClobClient`] and
+/// [`PaperExchange`](crate::PaperExchange).
+///
+/// Strategies written against `dyn OrderVenue` can switch between live and paper
+/// trading by choosing which implementation to construct. Responses have the same
+/// JSON shape in both cases.
+#[async_trait]
+pub trait OrderVenue: Send + Sync {
+    async fn post_order(
+        &self,
+        order: SignedOrderRequest,
+        order_type: OrderType,
+    ) -> ClientResult<Value>;
+
+    async fn cancel(&self, order_id: &str) -> ClientResult<Value>;
+
+    async fn cancel_orders(&self, order_ids: &[String]) -> ClientResult<Value>;
+
+    async fn cancel_all(&self) -> ClientResult<Value>;
+
+    async fn cancel_market_orders(
+        &self,
+        market: Option<&str>,
+        asset_id: Option<&str>,
+    ) -> ClientResult<Value>;
+
+    async fn get_order(&self, order_id: &str) -> ClientResult<Option<OpenOrder>>;
+
+    async fn get_orders(
+        &self,
+        params: Option<&OpenOrderParams>,
+        next_cursor: Option<&str>,
+    ) -> ClientResult<Vec<OpenOrder>>;
+
+    async fn get_trades(
+        &self,
+        trade_params: Option<&TradeParams>,
+        next_cursor: Option<&str>,
+    ) -> ClientResult<Vec<Value>>;
+}
+
+#[async_trait]
+impl OrderVenue for ClobClient {
+    async fn post_order(
+        &self,
+        order: SignedOrderRequest,
+        order_type: OrderType,
+    ) -> ClientResult<Value> {
+        ClobClient::post_order(self, order, order_type).await
+    }
+
+    async fn cancel(&self, order_id: &str) -> ClientResult<Value> {
+        ClobClient::cancel(self, order_id).await
+    }
+
+    async fn cancel_orders(&self, order_ids: &[String]) -> ClientResult<Value> {
+        ClobClient::cancel_orders(self, order_ids).await
+    }
+
+    async fn cancel_all(&self) -> ClientResult<Value> {
+        ClobClient::cancel_all(self).await
+    }
+
+    async fn cancel_market_orders(
+        &self,
+        market: Option<&str>,
+        asset_id: Option<&str>,
+    ) -> ClientResult<Value> {
+        ClobClient::cancel_market_orders(self, market, asset_id).await
+    }
+
+    async fn get_order(&self, order_id: &str) -> ClientResult<Option<OpenOrder>> {
+        ClobClient::get_order(self, order_id).await
+    }
+
+    async fn get_orders(
+        &self,
+        params: Option<&OpenOrderParams>,
+        next_cursor: Option<&str>,
+    ) -> ClientResult<Vec<OpenOrder>> {
+        ClobClient::get_orders(self, params, next_cursor).await
+    }
+
+    async fn get_trades(
+        &self,
+        trade_params: Option<&TradeParams>,
+        next_cursor: Option<&str>,
+    ) -> ClientResult<Vec<Value>> {
+        ClobClient::get_trades(self, trade_params, next_cursor).await
+    }
+}
+
+#[async_trait]
+impl<V: OrderVenue + ?Sized> OrderVenue for Arc<V> {
+    async fn post_order(
+        &self,
+        order: SignedOrderRequest,
+        order_type: OrderType,
+    ) -> ClientResult<Value> {
+        (**self).post_order(order, order_type).await
+    }
+
+    async fn cancel(&self, order_id: &str) -> ClientResult<Value> {
+        (**self).cancel(order_id).await
+    }
+
+    async fn cancel_orders(&self, order_ids: &[String]) -> ClientResult<Value> {
+        (**self).cancel_orders(order_ids).await
+    }
+
+    async fn cancel_all(&self) -> ClientResult<Value> {
+        (**self).cancel_all().await
+    }
+
+    async fn cancel_market_orders(
+        &self,
+        market: Option<&str>,
+        asset_id: Option<&str>,
+    ) -> ClientResult<Value> {
+        (**self).cancel_market_orders(market, asset_id).await
+    }
+
+    async fn get_order(&self, order_id: &str) -> ClientResult<Option<OpenOrder>> {
+        (**self).get_order(order_id).await
+    }
+
+    async fn get_orders(
+        &self,
+        params: Option<&OpenOrderParams>,
+        next_cursor: Option<&str>,
+    ) -> ClientResult<Vec<OpenOrder>> {
+        (**self).get_orders(params, next_cursor).await
+    }
+
+    async fn get_trades(
+        &self,
+        trade_params: Option<&TradeParams>,
+        next_cursor: Option<&str>,
+    ) -> ClientResult<Vec<Value>> {
+        (**self).get_trades(trade_params, next_cursor).await
+    }
+}
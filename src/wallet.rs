@@ -0,0 +1,83 @@
+//! Helpers for building a signer without handling a plaintext private key.
+//!
+//! The returned signer can be passed to [`ClobClient::with_signer`](crate::ClobClient::with_signer).
+
+use crate::{ClientResult, PrivateKeySigner};
+
+#[cfg(feature = "keystore")]
+use std::path::Path;
+
+/// Decrypts a JSON (Web3 Secret Storage) keystore with the given password.
+#[cfg(feature = "keystore")]
+pub fn signer_from_keystore(
+    path: impl AsRef<Path>,
+    password: impl AsRef<[u8]>,
+) -> ClientResult<PrivateKeySigner> {
+    let path = path.as_ref();
+    PrivateKeySigner::decrypt_keystore(path, password)
+        .map_err(|e| anyhow::anyhow!("Failed to decrypt keystore {}: {e}", path.display()))
+}
+
+/// Derives a signer from an English BIP-39 mnemonic.
+/// `derivation_path` defaults to the first Ethereum account, `m/44'/60'/0'/0/0`.
+#[cfg(feature = "mnemonic")]
+pub fn signer_from_mnemonic(
+    phrase: &str,
+    derivation_path: Option<&str>,
+) -> ClientResult<PrivateKeySigner> {
+    use alloy_signer_local::{coins_bip39::English, MnemonicBuilder};
+
+    let mut builder = MnemonicBuilder::<English>::default().phrase(phrase);
+    if let Some(path) = derivation_path {
+        builder = builder
+            .derivation_path(path)
+            .map_err(|e| anyhow::anyhow!("Invalid derivation path {path}: {e}"))?;
+    }
+    builder
+        .build()
+        .map_err(|e| anyhow::anyhow!("Failed to derive signer from mnemonic: {e}"))
+}
+
+#[cfg(all(test, any(feature = "keystore", feature = "mnemonic")))]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "keystore")]
+    #[test]
+    fn test_signer_from_keystore() {
+        let dir = std::env::temp_dir().join(format!("polymarket-keystore-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (signer, _) = PrivateKeySigner::encrypt_keystore(
+            &dir,
+            &mut rand::thread_rng(),
+            [7u8; 32],
+            "hunter2",
+            Some("key.json"),
+        )
+        .unwrap();
+
+        let loaded = signer_from_keystore(dir.join("key.json"), "hunter2").unwrap();
+        assert_eq!(loaded.address(), signer.address());
+        assert!(signer_from_keystore(dir.join("key.json"), "wrong").is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "mnemonic")]
+    #[test]
+    fn test_signer_from_mnemonic() {
+        let phrase = "test test test test test test test test test test test junk";
+
+        let first = signer_from_mnemonic(phrase, None).unwrap();
+        assert_eq!(
+            first.address().to_checksum(None),
+            "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"
+        );
+
+        let second = signer_from_mnemonic(phrase, Some("m/44'/60'/0'/0/1")).unwrap();
+        assert_eq!(
+            second.address().to_checksum(None),
+            "0x70997970C51812dc3A010C7d01b50e0d17dc79C8"
+        );
+    }
+}